@@ -1,8 +1,17 @@
 # BLAKE3 WASM Build (auditable) - Windows PowerShell
 $ErrorActionPreference = "Stop"
 
+# Also the blake3 crate version pinned in both Cargo.toml files below, and
+# BLAKE3_VERSION in both lib.rs files.
 $BLAKE3_TAG = "1.8.3"
 $BLAKE3_REPO = "https://github.com/BLAKE3-team/BLAKE3.git"
+# Optional API groups for the single-threaded module: "bao" (encoding,
+# slices, verified decoding), "streams" (async, Blob, ReadableStream and
+# HashingSink), "hazmat" (chunk and subtree CVs, CvStack) and, off by
+# default, "node" (async iterables such as Node Readable streams) and "cdc"
+# (FastCDC content-defined chunking). Set $env:FEATURES = "" for a build
+# without the optional groups.
+$Features = if ($null -ne $env:FEATURES) { $env:FEATURES } else { "bao streams hazmat" }
 $ScriptDir = Split-Path -Parent $MyInvocation.MyCommand.Path
 
 # Set up MSVC environment for native dependencies
@@ -24,6 +33,7 @@ $env:INCLUDE = "$msvcInclude;$sdkInclude\ucrt;$sdkInclude\um;$sdkInclude\shared"
 
 Write-Host "=== BLAKE3 WASM Build (auditable) ==="
 Write-Host "BLAKE3 version: $BLAKE3_TAG"
+Write-Host "Features: $(if ($Features) { $Features } else { 'none' })"
 Write-Host ""
 
 # --- Clone BLAKE3 source at pinned tag ---
@@ -46,7 +56,27 @@ crate-type = ["cdylib"]
 
 [dependencies]
 wasm-bindgen = "0.2"
-blake3 = { version = "1", features = ["wasm32_simd"] }
+blake3 = { version = "=1.8.3", features = ["wasm32_simd", "zeroize"] }
+bao = { version = "0.13", optional = true }
+base64 = "0.22"
+serde = { version = "1", features = ["derive"] }
+serde-wasm-bindgen = "0.6"
+talc = { version = "4", default-features = false, features = ["lock_api"], optional = true }
+fastcdc = { version = "3", optional = true }
+js-sys = "0.3"
+zeroize = "1"
+wasm-bindgen-futures = { version = "0.4", optional = true }
+web-sys = { version = "0.3", optional = true, features = ["Blob", "ReadableStream", "ReadableStreamDefaultReader", "ReadableWritablePair", "TransformStreamDefaultController"] }
+
+# Optional API groups; see FEATURES at the top of build.sh.
+[features]
+default = ["bao", "streams", "hazmat"]
+bao = ["dep:bao"]
+hazmat = []
+streams = ["dep:wasm-bindgen-futures", "dep:web-sys"]
+node = ["dep:wasm-bindgen-futures"]
+small-alloc = ["dep:talc"]
+cdc = ["dep:fastcdc"]
 
 [profile.release]
 lto = true
@@ -59,17 +89,11 @@ opt-level = 3
 rustflags = ["-C", "target-feature=+simd128"]
 "@ | Set-Content blake3-wasm-single/.cargo/config.toml -Encoding UTF8
 
-@"
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-pub fn hash(input: &[u8]) -> Vec<u8> {
-    blake3::hash(input).as_bytes().to_vec()
-}
-"@ | Set-Content blake3-wasm-single/src/lib.rs -Encoding UTF8
+# The Rust sources are checked in under src/ and copied in unchanged.
+Copy-Item (Join-Path $ScriptDir "src/single/lib.rs") blake3-wasm-single/src/lib.rs
 
 Push-Location blake3-wasm-single
-wasm-pack build --release --target web --out-dir pkg
+wasm-pack build --release --target web --out-dir pkg -- --no-default-features "--features=$Features"
 Pop-Location
 
 # --- Build parallel (rayon) module ---
@@ -88,8 +112,10 @@ crate-type = ["cdylib"]
 [dependencies]
 wasm-bindgen = "0.2"
 rayon = "1.8"
+js-sys = "0.3"
 wasm-bindgen-rayon = "1.3"
-blake3 = { version = "1", features = ["rayon", "wasm32_simd"] }
+blake3 = { version = "=1.8.3", features = ["rayon", "wasm32_simd", "zeroize"] }
+zeroize = "1"
 
 [profile.release]
 lto = true
@@ -123,30 +149,7 @@ targets = ["wasm32-unknown-unknown"]
 profile = "minimal"
 "@ | Set-Content blake3-wasm-rayon/rust-toolchain.toml -Encoding UTF8
 
-@"
-use wasm_bindgen::prelude::*;
-
-pub use wasm_bindgen_rayon::init_thread_pool;
-
-const OUT_LEN: usize = 32;
-const PAR_THRESHOLD: usize = 16 * 1024;
-
-#[wasm_bindgen]
-pub fn hash(input: &[u8]) -> Vec<u8> {
-    let mut hasher = blake3::Hasher::new();
-
-    if input.len() >= PAR_THRESHOLD {
-        hasher.update_rayon(input);
-    } else {
-        hasher.update(input);
-    }
-
-    let h = hasher.finalize();
-    let mut out = Vec::with_capacity(OUT_LEN);
-    out.extend_from_slice(h.as_bytes());
-    out
-}
-"@ | Set-Content blake3-wasm-rayon/src/lib.rs -Encoding UTF8
+Copy-Item (Join-Path $ScriptDir "src/rayon/lib.rs") blake3-wasm-rayon/src/lib.rs
 
 Push-Location blake3-wasm-rayon
 wasm-pack build --release --target web --out-dir pkg
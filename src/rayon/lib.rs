@@ -0,0 +1,114 @@
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroize;
+
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+const OUT_LEN: usize = 32;
+const BLAKE3_VERSION: &str = "1.8.3";
+const PAR_THRESHOLD: usize = 16 * 1024;
+
+#[wasm_bindgen]
+pub fn hash(input: &[u8]) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new();
+
+    if input.len() >= PAR_THRESHOLD {
+        hasher.update_rayon(input);
+    } else {
+        hasher.update(input);
+    }
+
+    let h = hasher.finalize();
+    let mut out = Vec::with_capacity(OUT_LEN);
+    out.extend_from_slice(h.as_bytes());
+    out
+}
+
+#[wasm_bindgen]
+pub fn simd_enabled() -> bool {
+    cfg!(target_feature = "simd128")
+}
+
+// The single-threaded WasmHasher plus update_parallel, which spreads one
+// large update across the thread pool.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmHasher {
+    inner: blake3::Hasher,
+}
+
+#[wasm_bindgen]
+impl WasmHasher {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmHasher {
+        WasmHasher::default()
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input);
+    }
+
+    // Below PAR_THRESHOLD this is the same as update.
+    pub fn update_parallel(&mut self, input: &[u8]) {
+        if input.len() >= PAR_THRESHOLD {
+            self.inner.update_rayon(input);
+        } else {
+            self.inner.update(input);
+        }
+    }
+
+    pub fn finalize(&self) -> Vec<u8> {
+        self.inner.finalize().as_bytes().to_vec()
+    }
+
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+// As in the single-threaded module, the hasher wipes its state when freed.
+impl Drop for WasmHasher {
+    fn drop(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+#[wasm_bindgen]
+pub fn capabilities() -> Result<Object, JsValue> {
+    let caps = Object::new();
+    Reflect::set(&caps, &"simd".into(), &simd_enabled().into())?;
+    Reflect::set(&caps, &"threads".into(), &true.into())?;
+    Reflect::set(&caps, &"features".into(), &Array::new())?;
+    Reflect::set(&caps, &"blake3_version".into(), &BLAKE3_VERSION.into())?;
+    Reflect::set(
+        &caps,
+        &"chunk_len".into(),
+        &(blake3::CHUNK_LEN as u32).into(),
+    )?;
+    Ok(caps)
+}
+
+fn now_ms() -> Result<f64, JsValue> {
+    let performance = Reflect::get(&js_sys::global(), &"performance".into())?;
+    let now: Function = Reflect::get(&performance, &"now".into())?.dyn_into()?;
+    Ok(now.call0(&performance)?.as_f64().unwrap_or_default())
+}
+
+// Same measurement as the single-threaded module, through the parallel hash
+// path, so it reflects the thread pool size passed to initThreadPool.
+#[wasm_bindgen]
+pub fn benchmark(num_bytes: usize, iterations: u32) -> Result<f64, JsValue> {
+    if num_bytes == 0 || iterations == 0 {
+        return Err(JsError::new("num_bytes and iterations must be non-zero").into());
+    }
+    let input: Vec<u8> = (0..num_bytes).map(|i| i as u8).collect();
+    let start = now_ms()?;
+    for _ in 0..iterations {
+        std::hint::black_box(hash(std::hint::black_box(&input)));
+    }
+    let seconds = (now_ms()? - start) / 1000.0;
+    if seconds <= 0.0 {
+        return Err(JsError::new("run too short to time; raise num_bytes or iterations").into());
+    }
+    Ok(num_bytes as f64 * iterations as f64 / seconds / 1e6)
+}
@@ -0,0 +1,5208 @@
+#[cfg(not(all(feature = "small-alloc", target_arch = "wasm32")))]
+use std::alloc::System;
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+#[cfg(feature = "bao")]
+use std::io::{Cursor, Read, Write};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+#[cfg(feature = "bao")]
+use bao::decode::SliceDecoder;
+#[cfg(feature = "bao")]
+use bao::encode::SliceExtractor;
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL};
+use base64::Engine;
+use blake3::hazmat::{self, ChainingValue, HasherExt, Mode};
+use blake3::{CHUNK_LEN, OUT_LEN};
+#[cfg(feature = "cdc")]
+use fastcdc::v2020::FastCDC;
+use js_sys::{Array, ArrayBuffer, Function, Object, Promise, Reflect, Uint8Array, WebAssembly};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+#[cfg(any(feature = "streams", feature = "node"))]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "streams")]
+use web_sys::{
+    Blob, ReadableStream, ReadableStreamDefaultReader, TransformStreamDefaultController,
+};
+use zeroize::{Zeroize, Zeroizing};
+
+const BLAKE3_VERSION: &str = "1.8.3";
+#[cfg(feature = "bao")]
+const HEADER_LEN: usize = 8;
+const ARRAY_BLOCK_LEN: u32 = 64 * 1024;
+#[cfg(feature = "streams")]
+const BLOB_SLICE_LEN: f64 = 4.0 * 1024.0 * 1024.0;
+const MULTIHASH_BLAKE3: u8 = 0x1e;
+const MULTICODEC_RAW: u8 = 0x55;
+const MULTI_DIGEST_BLOCK_LEN: usize = 16 * 1024;
+const STACK_WIPE_LEN: usize = 64 * 1024;
+const MANIFEST_MAGIC: &[u8] = b"blake3-wasm manifest v1\n";
+#[cfg(feature = "bao")]
+const VERIFIER_STATE_MAGIC: &[u8] = b"blake3-wasm verifier state v1\n";
+const OUTPUT_READER_STATE_MAGIC: &[u8] = b"blake3-wasm output reader state v1\n";
+const OUTPUT_READER_STATE_CONTEXT: &str = "blake3-wasm 2026-10-14 OutputReader state v1";
+#[cfg(feature = "bao")]
+const PARENT_LEN: usize = 2 * OUT_LEN;
+
+// Wraps the heap allocator to count live and peak heap bytes for
+// memory_stats, to enforce set_alloc_limit and for set_wipe_on_free.
+struct CountingAlloc<A>(A);
+
+static HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOC_LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+static WIPE_ON_FREE: AtomicBool = AtomicBool::new(false);
+
+fn within_limit(size: usize) -> bool {
+    HEAP_BYTES.load(Ordering::Relaxed).saturating_add(size) <= ALLOC_LIMIT.load(Ordering::Relaxed)
+}
+
+fn count_alloc(size: usize) {
+    let now = HEAP_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_HEAP_BYTES.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !within_limit(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ptr = self.0.alloc(layout);
+        if !ptr.is_null() {
+            count_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if !within_limit(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ptr = self.0.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            count_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if WIPE_ON_FREE.load(Ordering::Relaxed) {
+            std::slice::from_raw_parts_mut(ptr, layout.size()).zeroize();
+        }
+        self.0.dealloc(ptr, layout);
+        HEAP_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Moving in place could leave the old bytes behind, so copy and free
+        // through dealloc instead.
+        if WIPE_ON_FREE.load(Ordering::Relaxed) {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_ptr = self.alloc(new_layout);
+            if !new_ptr.is_null() {
+                std::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                self.dealloc(ptr, layout);
+            }
+            return new_ptr;
+        }
+        if new_size > layout.size() && !within_limit(new_size - layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let new_ptr = self.0.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            HEAP_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            count_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(not(all(feature = "small-alloc", target_arch = "wasm32")))]
+#[global_allocator]
+static ALLOCATOR: CountingAlloc<System> = CountingAlloc(System);
+
+// talc in place of the default dlmalloc: smaller code, same throughput for
+// hashing; see README. Sound because this module is single-threaded.
+#[cfg(all(feature = "small-alloc", target_arch = "wasm32"))]
+#[global_allocator]
+static ALLOCATOR: CountingAlloc<talc::TalckWasm> =
+    CountingAlloc(unsafe { talc::TalckWasm::new_global() });
+
+// Past the limit the allocator refuses to grow the heap. Buffers sized by a
+// caller-supplied length go through these checks and fail with an error; any
+// other refused allocation traps.
+#[wasm_bindgen]
+pub fn set_alloc_limit(bytes: Option<usize>) {
+    ALLOC_LIMIT.store(bytes.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+// Zeroes every heap block as it is freed, including the copies wasm-bindgen
+// makes of arguments, so hashed secrets don't linger in linear memory. Costs
+// a write per freed byte. Memory freed before it is turned on is not wiped.
+#[wasm_bindgen]
+pub fn set_wipe_on_free(enabled: bool) {
+    WIPE_ON_FREE.store(enabled, Ordering::Relaxed);
+}
+
+// Hashing also leaves copies of input blocks and chaining values on the wasm
+// stack. Called from JS, this frame sits where those calls' frames were, so
+// zeroing a region below it scrubs them.
+#[wasm_bindgen]
+#[inline(never)]
+pub fn wipe_stack() {
+    let mut scratch = [0u8; STACK_WIPE_LEN];
+    scratch.zeroize();
+    std::hint::black_box(&scratch);
+}
+
+fn check_alloc(len: u64) -> Result<(), JsError> {
+    if usize::try_from(len).map_or(true, |len| !within_limit(len)) {
+        return Err(JsError::new(&format!(
+            "allocating {len} bytes would exceed the allocation limit"
+        )));
+    }
+    Ok(())
+}
+
+fn output_buffer(len: usize) -> Result<Vec<u8>, JsError> {
+    check_alloc(len as u64)?;
+    let mut out = Vec::new();
+    out.try_reserve_exact(len)
+        .map_err(|_| JsError::new(&format!("cannot allocate {len} bytes")))?;
+    out.resize(len, 0);
+    Ok(out)
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(message: &str);
+}
+
+thread_local! {
+    static PANIC_CALLBACK: RefCell<Option<Function>> = const { RefCell::new(None) };
+}
+
+// wasm32 cannot unwind, so a panic still ends in an unreachable trap (a
+// catchable WebAssembly.RuntimeError). The hook reports the message and
+// location first, to on_panic if given and console.error otherwise.
+#[wasm_bindgen]
+pub fn init(on_panic: Option<Function>) {
+    PANIC_CALLBACK.with(|callback| *callback.borrow_mut() = on_panic);
+    std::panic::set_hook(Box::new(|info| {
+        let message = info.to_string();
+        let callback = PANIC_CALLBACK.with(|callback| callback.try_borrow().ok()?.clone());
+        match callback {
+            Some(callback) => {
+                let _ = callback.call1(&JsValue::NULL, &message.into());
+            }
+            None => console_error(&message),
+        }
+    }));
+}
+
+// Opt-in counters for attributing time between JS and wasm. Only the hashing
+// entry points are metered, each under its own name; wasm_ms is wall time
+// inside them, measured with performance.now().
+#[derive(Default)]
+struct Metrics {
+    bytes_hashed: u64,
+    calls: BTreeMap<&'static str, u64>,
+    wasm_ms: f64,
+}
+
+thread_local! {
+    static METRICS: RefCell<Option<Metrics>> = const { RefCell::new(None) };
+}
+
+fn metrics_enabled() -> bool {
+    METRICS.with_borrow(Option::is_some)
+}
+
+fn metered<T>(name: &'static str, bytes: usize, f: impl FnOnce() -> T) -> T {
+    if !metrics_enabled() {
+        return f();
+    }
+    let start = now_ms().unwrap_or_default();
+    let out = f();
+    let elapsed = now_ms().unwrap_or_default() - start;
+    METRICS.with_borrow_mut(|metrics| {
+        if let Some(metrics) = metrics {
+            metrics.bytes_hashed += bytes as u64;
+            *metrics.calls.entry(name).or_default() += 1;
+            metrics.wasm_ms += elapsed;
+        }
+    });
+    out
+}
+
+// Starts counting from zero, also when already enabled.
+#[wasm_bindgen]
+pub fn enable_metrics() {
+    METRICS.set(Some(Metrics::default()));
+}
+
+#[wasm_bindgen]
+pub fn disable_metrics() {
+    METRICS.set(None);
+}
+
+// { bytes_hashed, calls, wasm_ms }, with calls mapping export names to call
+// counts; undefined while metrics are disabled.
+#[wasm_bindgen]
+pub fn metrics() -> Result<JsValue, JsValue> {
+    METRICS.with_borrow(|metrics| {
+        let Some(metrics) = metrics else {
+            return Ok(JsValue::UNDEFINED);
+        };
+        let calls = Object::new();
+        for (&name, &count) in &metrics.calls {
+            Reflect::set(&calls, &name.into(), &(count as f64).into())?;
+        }
+        let out = Object::new();
+        Reflect::set(
+            &out,
+            &"bytes_hashed".into(),
+            &(metrics.bytes_hashed as f64).into(),
+        )?;
+        Reflect::set(&out, &"calls".into(), &calls)?;
+        Reflect::set(&out, &"wasm_ms".into(), &metrics.wasm_ms.into())?;
+        Ok(out.into())
+    })
+}
+
+#[wasm_bindgen]
+extern "C" {
+    // A 64-bit offset or length: a BigInt, or a Number that is a safe integer.
+    #[wasm_bindgen(typescript_type = "number | bigint")]
+    pub type U64Arg;
+}
+
+fn u64_arg(value: U64Arg, name: &str) -> Result<u64, JsError> {
+    let value = JsValue::from(value);
+    if value.is_bigint() {
+        return u64::try_from(value)
+            .map_err(|_| JsError::new(&format!("{name} must be between 0 and 2^64 - 1")));
+    }
+    match value.as_f64() {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= js_sys::Number::MAX_SAFE_INTEGER => {
+            Ok(n as u64)
+        }
+        Some(_) => Err(JsError::new(&format!(
+            "{name} must be a non-negative safe integer; pass a BigInt beyond 2^53"
+        ))),
+        None => Err(JsError::new(&format!("{name} must be a Number or BigInt"))),
+    }
+}
+
+fn js_number(value: u128, name: &str) -> Result<f64, JsError> {
+    if value > js_sys::Number::MAX_SAFE_INTEGER as u128 {
+        return Err(JsError::new(&format!(
+            "{name} can't be represented as a JavaScript number"
+        )));
+    }
+    Ok(value as f64)
+}
+
+// A 32-byte hash or CV in a serialized result; serde-wasm-bindgen turns
+// bytes into a Uint8Array rather than an array of numbers.
+#[cfg(feature = "hazmat")]
+struct Cv([u8; OUT_LEN]);
+
+#[cfg(feature = "hazmat")]
+impl Serialize for Cv {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+// A chunk's CV and its index in the input, as the tree exports return them.
+#[cfg(feature = "hazmat")]
+#[derive(Serialize)]
+struct ChunkCv {
+    index: u64,
+    cv: Cv,
+}
+
+// Results go to JS as plain objects with camelCase keys. u64 fields become
+// Numbers, and values past 2^53 throw rather than losing precision.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(value)?)
+}
+
+fn key_array(key: &[u8]) -> Result<&[u8; blake3::KEY_LEN], JsError> {
+    key.try_into()
+        .map_err(|_| JsError::new("key must be 32 bytes"))
+}
+
+fn parse_hash(bytes: &[u8]) -> Result<blake3::Hash, JsError> {
+    blake3::Hash::from_slice(bytes).map_err(|_| JsError::new("hash must be 32 bytes"))
+}
+
+// Copies the array into wasm memory one block at a time, so peak memory
+// stays at ARRAY_BLOCK_LEN instead of growing with the input.
+fn update_from_array(hasher: &mut blake3::Hasher, input: &Uint8Array) {
+    let mut block = vec![0; ARRAY_BLOCK_LEN as usize];
+    let len = input.length();
+    let mut start = 0;
+    while start < len {
+        let end = len.min(start + ARRAY_BLOCK_LEN);
+        let block = &mut block[..(end - start) as usize];
+        input.subarray(start, end).copy_to(block);
+        hasher.update(block);
+        start = end;
+    }
+}
+
+#[cfg(any(feature = "streams", feature = "node"))]
+fn byte_chunk(chunk: JsValue) -> Result<Uint8Array, JsError> {
+    chunk
+        .dyn_into()
+        .map_err(|_| JsError::new("stream chunks must be Uint8Array"))
+}
+
+#[wasm_bindgen]
+pub fn hash(input: &[u8]) -> Vec<u8> {
+    metered("hash", input.len(), || {
+        blake3::hash(input).as_bytes().to_vec()
+    })
+}
+
+#[wasm_bindgen]
+pub fn hash_array(input: &Uint8Array) -> Vec<u8> {
+    metered("hash_array", input.length() as usize, || {
+        let mut hasher = blake3::Hasher::new();
+        update_from_array(&mut hasher, input);
+        hasher.finalize().as_bytes().to_vec()
+    })
+}
+
+// A view onto another module's linear memory. Checked against the current
+// buffer, which is replaced whenever that memory grows.
+fn memory_view(memory: &WebAssembly::Memory, offset: u32, len: u32) -> Result<Uint8Array, JsError> {
+    let buffer = memory.buffer();
+    let memory_len = Reflect::get(&buffer, &"byteLength".into())
+        .ok()
+        .and_then(|len| len.as_f64())
+        .unwrap_or_default() as u64;
+    if u64::from(offset) + u64::from(len) > memory_len {
+        return Err(JsError::new(&format!(
+            "range {offset}+{len} is outside the {memory_len}-byte memory"
+        )));
+    }
+    Ok(Uint8Array::new_with_byte_offset_and_length(
+        &buffer, offset, len,
+    ))
+}
+
+// Hashes len bytes at offset in an imported WebAssembly.Memory, copying them
+// into this module in 64 KB blocks rather than all at once.
+#[wasm_bindgen]
+pub fn hash_memory(
+    memory: &WebAssembly::Memory,
+    offset: u32,
+    len: u32,
+) -> Result<Vec<u8>, JsError> {
+    let input = memory_view(memory, offset, len)?;
+    Ok(metered("hash_memory", len as usize, || {
+        let mut hasher = blake3::Hasher::new();
+        update_from_array(&mut hasher, &input);
+        hasher.finalize().as_bytes().to_vec()
+    }))
+}
+
+// Total length of the Uint8Arrays in inputs, for metered; only walked while
+// metrics are enabled.
+fn metered_array_bytes(inputs: &Array) -> usize {
+    if !metrics_enabled() {
+        return 0;
+    }
+    inputs
+        .iter()
+        .filter_map(|input| Some(input.dyn_into::<Uint8Array>().ok()?.length() as usize))
+        .sum()
+}
+
+// One call for many small inputs; each is copied into a reused buffer, and
+// the digests are concatenated in input order.
+#[wasm_bindgen]
+pub fn hash_many(inputs: &Array) -> Result<Vec<u8>, JsError> {
+    metered("hash_many", metered_array_bytes(inputs), || {
+        let mut digests = Vec::with_capacity(inputs.length() as usize * OUT_LEN);
+        let mut buffer = Vec::new();
+        for input in inputs.iter() {
+            let input: Uint8Array = input
+                .dyn_into()
+                .map_err(|_| JsError::new("inputs must be Uint8Array"))?;
+            buffer.resize(input.length() as usize, 0);
+            input.copy_to(&mut buffer);
+            digests.extend_from_slice(blake3::hash(&buffer).as_bytes());
+        }
+        Ok(digests)
+    })
+}
+
+// Checks each input against its digest in expected_digests, laid out as
+// hash_many returns them, and returns the indices of the inputs that don't
+// match; an empty result means every input verified.
+#[wasm_bindgen]
+pub fn verify_files(inputs: &Array, expected_digests: &[u8]) -> Result<Vec<u32>, JsError> {
+    if expected_digests.len() != inputs.length() as usize * OUT_LEN {
+        return Err(JsError::new(
+            "expected_digests must hold one 32-byte digest per input",
+        ));
+    }
+    metered("verify_files", metered_array_bytes(inputs), || {
+        let mut failures = Vec::new();
+        let mut buffer = Vec::new();
+        for ((index, input), expected) in (0..)
+            .zip(inputs.iter())
+            .zip(expected_digests.chunks_exact(OUT_LEN))
+        {
+            let input: Uint8Array = input
+                .dyn_into()
+                .map_err(|_| JsError::new("inputs must be Uint8Array"))?;
+            buffer.resize(input.length() as usize, 0);
+            input.copy_to(&mut buffer);
+            if blake3::hash(&buffer) != parse_hash(expected)? {
+                failures.push(index);
+            }
+        }
+        Ok(failures)
+    })
+}
+
+// Hashes the segments as one contiguous input without joining them in JS;
+// each is copied in block by block, as in hash_array.
+#[wasm_bindgen]
+pub fn hash_gather(segments: &Array) -> Result<Vec<u8>, JsError> {
+    metered("hash_gather", metered_array_bytes(segments), || {
+        let mut hasher = blake3::Hasher::new();
+        for segment in segments.iter() {
+            let segment: Uint8Array = segment
+                .dyn_into()
+                .map_err(|_| JsError::new("segments must be Uint8Array"))?;
+            update_from_array(&mut hasher, &segment);
+        }
+        Ok(hasher.finalize().as_bytes().to_vec())
+    })
+}
+
+fn report_progress(progress: &Function, bytes_done: f64) -> Result<(), JsValue> {
+    progress.call1(&JsValue::NULL, &JsValue::from_f64(bytes_done))?;
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn hash_with_progress(
+    input: &[u8],
+    interval: usize,
+    progress: &Function,
+) -> Result<Vec<u8>, JsValue> {
+    if interval == 0 {
+        return Err(JsError::new("progress interval must be non-zero").into());
+    }
+    metered("hash_with_progress", input.len(), || {
+        let mut hasher = blake3::Hasher::new();
+        let mut done = 0;
+        for piece in input.chunks(interval) {
+            hasher.update(piece);
+            done += piece.len();
+            report_progress(progress, done as f64)?;
+        }
+        Ok(hasher.finalize().as_bytes().to_vec())
+    })
+}
+
+// Waits on a setTimeout(0) macrotask, which lets the page render and handle
+// events. A resolved promise would only yield to other microtasks.
+#[cfg(feature = "streams")]
+async fn yield_to_event_loop() -> Result<(), JsValue> {
+    let set_timeout: Function =
+        Reflect::get(&js_sys::global(), &"setTimeout".into())?.dyn_into()?;
+    let mut result = Ok(JsValue::UNDEFINED);
+    let promise = Promise::new(&mut |resolve, _| {
+        result = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0));
+    });
+    result?;
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+pub async fn hash_async(input: Uint8Array, slice_len: u32) -> Result<Vec<u8>, JsValue> {
+    if slice_len == 0 {
+        return Err(JsError::new("slice length must be non-zero").into());
+    }
+    let mut hasher = blake3::Hasher::new();
+    let len = input.length();
+    let mut start = 0;
+    while start < len {
+        let end = len.min(start.saturating_add(slice_len));
+        update_from_array(&mut hasher, &input.subarray(start, end));
+        start = end;
+        yield_to_event_loop().await?;
+    }
+    Ok(hasher.finalize().as_bytes().to_vec())
+}
+
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+pub async fn hash_blob(blob: Blob, progress: Option<Function>) -> Result<Vec<u8>, JsValue> {
+    let mut hasher = blake3::Hasher::new();
+    let size = blob.size();
+    let mut start = 0.0;
+    while start < size {
+        let end = size.min(start + BLOB_SLICE_LEN);
+        let slice = blob.slice_with_f64_and_f64(start, end)?;
+        let buffer = JsFuture::from(slice.array_buffer()).await?;
+        update_from_array(&mut hasher, &Uint8Array::new(&buffer));
+        start = end;
+        if let Some(progress) = &progress {
+            report_progress(progress, end)?;
+        }
+    }
+    Ok(hasher.finalize().as_bytes().to_vec())
+}
+
+// Returns the number of bytes read.
+#[cfg(feature = "streams")]
+async fn update_from_stream(
+    hasher: &mut blake3::Hasher,
+    stream: &ReadableStream,
+) -> Result<u64, JsValue> {
+    let reader: ReadableStreamDefaultReader = stream.get_reader().unchecked_into();
+    let mut len = 0;
+    loop {
+        let result = JsFuture::from(reader.read()).await?;
+        if Reflect::get(&result, &"done".into())?.is_truthy() {
+            break;
+        }
+        let chunk = byte_chunk(Reflect::get(&result, &"value".into())?)?;
+        update_from_array(hasher, &chunk);
+        len += u64::from(chunk.length());
+    }
+    Ok(len)
+}
+
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+pub async fn hash_stream(stream: ReadableStream) -> Result<Vec<u8>, JsValue> {
+    let mut hasher = blake3::Hasher::new();
+    update_from_stream(&mut hasher, &stream).await?;
+    Ok(hasher.finalize().as_bytes().to_vec())
+}
+
+// web-sys only has DecompressionStream behind web_sys_unstable_apis.
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+extern "C" {
+    type DecompressionStream;
+
+    #[wasm_bindgen(constructor, catch)]
+    fn new(format: &str) -> Result<DecompressionStream, JsValue>;
+}
+
+// Inflates through the platform DecompressionStream ("gzip", "deflate" or
+// "deflate-raw") and hashes the output as it arrives, so neither the
+// compressed nor the inflated bytes are buffered.
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+pub async fn hash_decompressed_stream(
+    stream: ReadableStream,
+    format: String,
+) -> Result<Object, JsValue> {
+    let decompressor = DecompressionStream::new(&format)?;
+    let inflated = stream.pipe_through(decompressor.unchecked_ref());
+    let mut hasher = blake3::Hasher::new();
+    let size = update_from_stream(&mut hasher, &inflated).await?;
+    let result = Object::new();
+    let digest = Uint8Array::from(hasher.finalize().as_bytes().as_slice());
+    Reflect::set(&result, &"digest".into(), &digest)?;
+    Reflect::set(
+        &result,
+        &"size".into(),
+        &js_number(size.into(), "size")?.into(),
+    )?;
+    Ok(result)
+}
+
+// Consumes any async iterable of Uint8Array chunks, such as a Node Readable.
+#[cfg(feature = "node")]
+#[wasm_bindgen]
+pub async fn hash_async_iterable(iterable: JsValue) -> Result<Vec<u8>, JsValue> {
+    let iterate: Function = Reflect::get(&iterable, &js_sys::Symbol::async_iterator().into())?
+        .dyn_into()
+        .map_err(|_| JsError::new("input is not async iterable"))?;
+    let iterator = iterate.call0(&iterable)?;
+    let next: Function = Reflect::get(&iterator, &"next".into())?.dyn_into()?;
+    let mut hasher = blake3::Hasher::new();
+    loop {
+        let result = JsFuture::from(Promise::resolve(&next.call0(&iterator)?)).await?;
+        if Reflect::get(&result, &"done".into())?.is_truthy() {
+            break;
+        }
+        let chunk = byte_chunk(Reflect::get(&result, &"value".into())?)?;
+        update_from_array(&mut hasher, &chunk);
+    }
+    Ok(hasher.finalize().as_bytes().to_vec())
+}
+
+#[wasm_bindgen]
+pub fn hash_hex(input: &[u8]) -> String {
+    metered("hash_hex", input.len(), || {
+        blake3::hash(input).to_hex().to_string()
+    })
+}
+
+#[wasm_bindgen]
+pub fn hash_string(input: &str) -> Vec<u8> {
+    metered("hash_string", input.len(), || {
+        blake3::hash(input.as_bytes()).as_bytes().to_vec()
+    })
+}
+
+#[wasm_bindgen]
+pub fn hash_string_hex(input: &str) -> String {
+    metered("hash_string_hex", input.len(), || {
+        blake3::hash(input.as_bytes()).to_hex().to_string()
+    })
+}
+
+#[wasm_bindgen]
+pub fn to_hex(digest: &[u8]) -> Result<String, JsError> {
+    Ok(parse_hash(digest)?.to_hex().to_string())
+}
+
+// blake3::Hash equality is constant-time.
+#[wasm_bindgen]
+pub fn digests_equal(a: &[u8], b: &[u8]) -> Result<bool, JsError> {
+    Ok(parse_hash(a)? == parse_hash(b)?)
+}
+
+#[wasm_bindgen]
+pub fn digest_to_sri(digest: &[u8]) -> Result<String, JsError> {
+    let digest = parse_hash(digest)?;
+    Ok(format!("blake3-{}", BASE64.encode(digest.as_bytes())))
+}
+
+fn parse_sri(integrity: &str) -> Result<blake3::Hash, JsError> {
+    let encoded = integrity
+        .trim()
+        .strip_prefix("blake3-")
+        .ok_or_else(|| JsError::new("integrity string must start with blake3-"))?;
+    parse_hash(&BASE64.decode(encoded)?)
+}
+
+#[wasm_bindgen]
+pub fn sri_to_digest(integrity: &str) -> Result<Vec<u8>, JsError> {
+    Ok(parse_sri(integrity)?.as_bytes().to_vec())
+}
+
+// Accepts an SRI string or a bare 64-digit hex digest, and compares in
+// constant time. A malformed integrity string throws rather than failing.
+#[wasm_bindgen]
+pub fn verify_integrity(data: &[u8], integrity: &str) -> Result<bool, JsError> {
+    let expected = match blake3::Hash::from_hex(integrity.trim()) {
+        Ok(digest) => digest,
+        Err(_) => parse_sri(integrity)?,
+    };
+    Ok(metered("verify_integrity", data.len(), || {
+        blake3::hash(data) == expected
+    }))
+}
+
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in bytes {
+        acc = ((acc << 8) | u32::from(b)) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(acc >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(acc << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+fn hex_lower(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(2 * bytes.len());
+    for &b in bytes {
+        out.push(DIGITS[usize::from(b >> 4)] as char);
+        out.push(DIGITS[usize::from(b & 15)] as char);
+    }
+    out
+}
+
+fn multihash(digest: blake3::Hash) -> Vec<u8> {
+    let mut out = vec![MULTIHASH_BLAKE3, OUT_LEN as u8];
+    out.extend_from_slice(digest.as_bytes());
+    out
+}
+
+#[wasm_bindgen]
+pub fn hash_multihash(input: &[u8]) -> Vec<u8> {
+    metered("hash_multihash", input.len(), || {
+        multihash(blake3::hash(input))
+    })
+}
+
+#[wasm_bindgen]
+pub fn digest_to_multihash(digest: &[u8]) -> Result<Vec<u8>, JsError> {
+    Ok(multihash(parse_hash(digest)?))
+}
+
+// CIDv1, raw codec, multibase base32 ("b" prefix), as printed by IPFS tools.
+#[wasm_bindgen]
+pub fn digest_to_cid(digest: &[u8]) -> Result<String, JsError> {
+    let mut cid = vec![1, MULTICODEC_RAW];
+    cid.extend(digest_to_multihash(digest)?);
+    Ok(format!("b{}", base32_lower(&cid)))
+}
+
+// One line of b3sum output: the hex digest, two spaces, the file name and a
+// newline. Like b3sum (and md5sum), a name holding a backslash, newline or
+// carriage return is escaped and the line starts with a backslash.
+#[wasm_bindgen]
+pub fn format_b3sum_line(digest: &[u8], filename: &str) -> Result<String, JsError> {
+    let hex = to_hex(digest)?;
+    if filename.contains(['\\', '\n', '\r']) {
+        let escaped = filename
+            .replace('\\', "\\\\")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        Ok(format!("\\{hex}  {escaped}\n"))
+    } else {
+        Ok(format!("{hex}  {filename}\n"))
+    }
+}
+
+// Accepts what b3sum --check accepts: "<hex>  <name>" or the --tag form
+// "BLAKE3 (<name>) = <hex>", lowercase hex only, no escapes other than \\, \n
+// and \r, and no NUL or U+FFFD (b3sum's stand-in for invalid UTF-8) in names.
+fn parse_b3sum_line(line: &str) -> Result<(blake3::Hash, String), &'static str> {
+    let line = line.trim_end_matches('\r');
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // A default-format line starts with a hex digit, so it can't begin with
+    // the tag; splitting it at the digest's fixed length keeps double spaces
+    // in either format's file names intact.
+    let (hex, name) = match line.strip_prefix("BLAKE3 (") {
+        Some(tagged) => {
+            let (name, hex) = tagged
+                .rsplit_once(") = ")
+                .ok_or("expected BLAKE3 (<file name>) = <digest>")?;
+            (hex, Some(name))
+        }
+        None => {
+            let hex = line
+                .get(..2 * OUT_LEN)
+                .ok_or("expected <digest>  <file name>")?;
+            (hex, line[hex.len()..].strip_prefix("  "))
+        }
+    };
+    if hex.len() != 2 * OUT_LEN || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return Err("digest must be 64 lowercase hex digits");
+    }
+    let name = name.ok_or("expected <digest>  <file name>")?;
+    let digest = blake3::Hash::from_hex(hex).unwrap();
+    let mut filename = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' || !escaped {
+            filename.push(c);
+            continue;
+        }
+        filename.push(match chars.next() {
+            Some('\\') => '\\',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            _ => return Err("invalid backslash escape in file name"),
+        });
+    }
+    if filename.is_empty() || filename.contains(['\0', '\u{fffd}']) {
+        return Err("invalid file name");
+    }
+    Ok((digest, filename))
+}
+
+// Returns one { digest, filename } object per line. Blank lines are errors,
+// as in b3sum, apart from the final newline.
+#[wasm_bindgen]
+pub fn parse_b3sum_output(text: &str) -> Result<Array, JsValue> {
+    let entries = Array::new();
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    if text.is_empty() {
+        return Ok(entries);
+    }
+    for (i, line) in text.split('\n').enumerate() {
+        let (digest, filename) =
+            parse_b3sum_line(line).map_err(|e| JsError::new(&format!("line {}: {e}", i + 1)))?;
+        let entry = Object::new();
+        Reflect::set(
+            &entry,
+            &"digest".into(),
+            &Uint8Array::from(&digest.as_bytes()[..]),
+        )?;
+        Reflect::set(&entry, &"filename".into(), &filename.into())?;
+        entries.push(&entry);
+    }
+    Ok(entries)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChunkRange {
+    start_chunk: u64,
+    end_chunk: u64,
+    start_offset: u64,
+    end_offset: u64,
+}
+
+// Chunks covering bytes [start, start + len): chunk indices [startChunk,
+// endChunk), with the range beginning startOffset bytes into the first and
+// ending endOffset bytes into the last. An empty range covers no chunks.
+#[wasm_bindgen]
+pub fn byte_range_to_chunks(start: U64Arg, len: U64Arg) -> Result<JsValue, JsValue> {
+    let (start, len) = (u64_arg(start, "start")?, u64_arg(len, "len")?);
+    let end = start
+        .checked_add(len)
+        .ok_or_else(|| JsError::new("start + len must not exceed 2^64 - 1"))?;
+    let chunk_len = CHUNK_LEN as u64;
+    let start_chunk = start / chunk_len;
+    let (end_chunk, start_offset, end_offset) = if len == 0 {
+        (start_chunk, 0, 0)
+    } else {
+        let end_chunk = end.div_ceil(chunk_len);
+        (
+            end_chunk,
+            start % chunk_len,
+            end - (end_chunk - 1) * chunk_len,
+        )
+    };
+    to_js(&ChunkRange {
+        start_chunk,
+        end_chunk,
+        start_offset,
+        end_offset,
+    })
+}
+
+// Bytes [start, end) of a content_len-byte input held by chunks [start_chunk,
+// end_chunk); the last chunk may be short, so both are clamped to content_len.
+#[wasm_bindgen]
+pub fn chunks_to_byte_range(
+    start_chunk: U64Arg,
+    end_chunk: U64Arg,
+    content_len: U64Arg,
+) -> Result<Object, JsValue> {
+    let start_chunk = u64_arg(start_chunk, "start_chunk")?;
+    let end_chunk = u64_arg(end_chunk, "end_chunk")?;
+    let content_len = u64_arg(content_len, "content_len")?;
+    if start_chunk > end_chunk {
+        return Err(JsError::new("start_chunk must not be after end_chunk").into());
+    }
+    let to_byte = |chunk: u64| content_len.min(chunk.saturating_mul(CHUNK_LEN as u64));
+    let range = Object::new();
+    for (name, value) in [("start", to_byte(start_chunk)), ("end", to_byte(end_chunk))] {
+        Reflect::set(&range, &name.into(), &js_number(value.into(), name)?.into())?;
+    }
+    Ok(range)
+}
+
+// Byte offset of chunk first_chunk_index, checked so that data_len bytes
+// from there still fit in a BLAKE3 input.
+#[cfg(feature = "hazmat")]
+fn chunk_offset(first_chunk_index: u64, data_len: usize, name: &str) -> Result<u64, JsError> {
+    first_chunk_index
+        .checked_mul(CHUNK_LEN as u64)
+        .filter(|offset| offset.checked_add(data_len as u64).is_some())
+        .ok_or_else(|| JsError::new(&format!("{name} is too far into the input")))
+}
+
+// The hazmat modes, for the tree exports' optional mode argument. The key
+// argument holds the key for KeyedHash and the derive_key context key for
+// DeriveKeyMaterial; without a mode, a key means KeyedHash.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HashMode {
+    Hash,
+    KeyedHash,
+    DeriveKeyMaterial,
+}
+
+// The mode the tree exports hash in, with its key, which is wiped on drop.
+#[cfg(feature = "hazmat")]
+#[derive(Default)]
+enum TreeMode {
+    #[default]
+    Hash,
+    Keyed(Zeroizing<[u8; blake3::KEY_LEN]>),
+    DeriveKey(Zeroizing<[u8; blake3::KEY_LEN]>),
+}
+
+#[cfg(feature = "hazmat")]
+impl TreeMode {
+    // The key may be undefined or null, or any ArrayBuffer or view of 32
+    // bytes, copied straight into wasm.
+    fn new(key: &JsValue, mode: Option<HashMode>) -> Result<TreeMode, JsError> {
+        let key = if key.is_undefined() || key.is_null() {
+            None
+        } else {
+            let source = buffer_source(key)?;
+            if source.length() as usize != blake3::KEY_LEN {
+                return Err(JsError::new("key must be 32 bytes"));
+            }
+            let mut bytes = Zeroizing::new([0; blake3::KEY_LEN]);
+            source.copy_to(&mut bytes[..]);
+            Some(bytes)
+        };
+        match (mode, key) {
+            (None | Some(HashMode::Hash), None) => Ok(TreeMode::Hash),
+            (None | Some(HashMode::KeyedHash), Some(key)) => Ok(TreeMode::Keyed(key)),
+            (Some(HashMode::DeriveKeyMaterial), Some(key)) => Ok(TreeMode::DeriveKey(key)),
+            (Some(HashMode::Hash), Some(_)) => Err(JsError::new("HashMode.Hash takes no key")),
+            (Some(HashMode::KeyedHash), None) => {
+                Err(JsError::new("HashMode.KeyedHash needs a 32-byte key"))
+            }
+            (Some(HashMode::DeriveKeyMaterial), None) => Err(JsError::new(
+                "HashMode.DeriveKeyMaterial needs the 32-byte context key as key",
+            )),
+        }
+    }
+
+    fn hasher(&self) -> blake3::Hasher {
+        match self {
+            TreeMode::Hash => blake3::Hasher::new(),
+            TreeMode::Keyed(key) => blake3::Hasher::new_keyed(key),
+            TreeMode::DeriveKey(context_key) => blake3::Hasher::new_from_context_key(context_key),
+        }
+    }
+
+    fn mode(&self) -> Mode<'_> {
+        match self {
+            TreeMode::Hash => Mode::Hash,
+            TreeMode::Keyed(key) => Mode::KeyedHash(key),
+            TreeMode::DeriveKey(context_key) => Mode::DeriveKeyMaterial(context_key),
+        }
+    }
+
+    // The non-root CV of a chunk, or of a subtree checked by check_subtree.
+    fn subtree_cv(&self, data: &[u8], input_offset: u64) -> ChainingValue {
+        self.hasher()
+            .set_input_offset(input_offset)
+            .update(data)
+            .finalize_non_root()
+    }
+}
+
+// The non-root CVs of the 1024-byte chunks of data (the last may be short),
+// as { index, cv } objects numbered from first_chunk_index. These are the
+// leaves of the tree; a whole input of one chunk has no parents, and its hash
+// is hash(data).
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn hash_chunks_batch(
+    data: &[u8],
+    first_chunk_index: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<JsValue, JsValue> {
+    let first_chunk_index = u64_arg(first_chunk_index, "first_chunk_index")?;
+    chunk_offset(first_chunk_index, data.len(), "first_chunk_index")?;
+    let tree = TreeMode::new(key, mode)?;
+    check_alloc((data.len().div_ceil(CHUNK_LEN) * size_of::<ChunkCv>()) as u64)?;
+    let cvs: Vec<ChunkCv> = metered("hash_chunks_batch", data.len(), || {
+        data.chunks(CHUNK_LEN)
+            .zip(first_chunk_index..)
+            .map(|(chunk, index)| ChunkCv {
+                index,
+                cv: Cv(tree.subtree_cv(chunk, index * CHUNK_LEN as u64)),
+            })
+            .collect()
+    });
+    to_js(&cvs)
+}
+
+// Checks one chunk against its expected CV, as hash_chunks_batch computes
+// it, comparing in constant time inside wasm.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn verify_chunk(
+    data: &[u8],
+    chunk_index: U64Arg,
+    expected_cv: &[u8],
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<bool, JsError> {
+    if data.is_empty() || data.len() > CHUNK_LEN {
+        return Err(JsError::new("data must be 1 to 1024 bytes"));
+    }
+    let chunk_index = u64_arg(chunk_index, "chunk_index")?;
+    let offset = chunk_offset(chunk_index, data.len(), "chunk_index")?;
+    let expected = parse_hash(expected_cv)?;
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("verify_chunk", data.len(), || {
+        expected == tree.subtree_cv(data, offset)
+    }))
+}
+
+// verify_chunk over every chunk of data against expected_cvs, in any layout
+// parse_cvs takes. Returns the positions in the batch (0 for the chunk at
+// first_chunk_index) of the chunks that don't match.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn verify_chunks_batch(
+    data: &[u8],
+    first_chunk_index: U64Arg,
+    expected_cvs: &JsValue,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u32>, JsError> {
+    let first_chunk_index = u64_arg(first_chunk_index, "first_chunk_index")?;
+    let offset = chunk_offset(first_chunk_index, data.len(), "first_chunk_index")?;
+    let expected_cvs = parse_cvs(expected_cvs, "expected_cvs")?;
+    if expected_cvs.len() != data.len().div_ceil(CHUNK_LEN) {
+        return Err(JsError::new(
+            "expected_cvs must hold one 32-byte CV per chunk of data",
+        ));
+    }
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("verify_chunks_batch", data.len(), || {
+        let mut failures = Vec::new();
+        for ((i, chunk), expected) in (0..).zip(data.chunks(CHUNK_LEN)).zip(&expected_cvs) {
+            let chunk_offset = offset + u64::from(i) * CHUNK_LEN as u64;
+            // Hash's PartialEq<[u8; 32]> compares in constant time.
+            if blake3::Hash::from(tree.subtree_cv(chunk, chunk_offset)) != *expected {
+                failures.push(i);
+            }
+        }
+        failures
+    }))
+}
+
+// Reads CVs as hash_chunks_batch returns them, an array of { index, cv }
+// objects, or as an array of bare 32-byte CVs, such as a build_tree level.
+// A Uint8Array of concatenated CVs is also taken.
+#[cfg(feature = "hazmat")]
+fn parse_cvs(cvs: &JsValue, name: &str) -> Result<Vec<ChainingValue>, JsError> {
+    if let Some(bytes) = cvs.dyn_ref::<Uint8Array>() {
+        let bytes = bytes.to_vec();
+        if !bytes.len().is_multiple_of(OUT_LEN) {
+            return Err(JsError::new(&format!(
+                "{name} must be a multiple of 32 bytes"
+            )));
+        }
+        return Ok(bytes
+            .chunks_exact(OUT_LEN)
+            .map(|cv| cv.try_into().unwrap())
+            .collect());
+    }
+    let cvs = cvs
+        .dyn_ref::<Array>()
+        .ok_or_else(|| JsError::new(&format!("{name} must be an array of CVs")))?;
+    (0..)
+        .zip(cvs.iter())
+        .map(|(i, entry)| {
+            let cv = if entry.is_instance_of::<Uint8Array>() {
+                entry
+            } else {
+                Reflect::get(&entry, &"cv".into()).unwrap_or_default()
+            };
+            cv.dyn_into::<Uint8Array>()
+                .ok()
+                .filter(|cv| cv.length() == OUT_LEN as u32)
+                .map(|cv| {
+                    let mut out = [0; OUT_LEN];
+                    cv.copy_to(&mut out);
+                    out
+                })
+                .ok_or_else(|| {
+                    JsError::new(&format!(
+                        "{name}[{i}] must be a 32-byte CV or an {{ index, cv }} object"
+                    ))
+                })
+        })
+        .collect()
+}
+
+// One level up the tree: each adjacent pair merges into its non-root parent,
+// and an odd CV at the end moves up unchanged. Level by level this gives the
+// canonical split, since every left subtree is a power of two chunks.
+#[cfg(feature = "hazmat")]
+fn merge_cv_level(cvs: &[ChainingValue], mode: Mode) -> Vec<ChainingValue> {
+    cvs.chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hazmat::merge_subtrees_non_root(left, right, mode),
+            _ => pair[0],
+        })
+        .collect()
+}
+
+// The next level of the tree from its child CVs, left to right, as an array
+// of 32-byte CVs. Every merge is non-root, so two CVs give their parent's
+// CV, not the root hash.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn parent_cv_level(
+    cvs: &JsValue,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<JsValue, JsValue> {
+    let cvs = parse_cvs(cvs, "cvs")?;
+    if cvs.is_empty() {
+        return Err(JsError::new("cvs must hold at least one CV").into());
+    }
+    let tree = TreeMode::new(key, mode)?;
+    let level: Vec<Cv> = metered("parent_cv_level", cvs.len() * OUT_LEN, || {
+        merge_cv_level(&cvs, tree.mode())
+            .into_iter()
+            .map(Cv)
+            .collect()
+    });
+    to_js(&level)
+}
+
+// Merges two or more leaf CVs up to the root hash.
+#[cfg(feature = "hazmat")]
+fn root_from_cvs(mut cvs: Vec<ChainingValue>, mode: Mode) -> blake3::Hash {
+    while cvs.len() > 2 {
+        cvs = merge_cv_level(&cvs, mode);
+    }
+    hazmat::merge_subtrees_root(&cvs[0], &cvs[1], mode)
+}
+
+// The CVs of every chunk of a total_len-byte input. An input of one chunk
+// or less has no parent to finish, and the CV of its only chunk doesn't
+// determine it.
+#[cfg(feature = "hazmat")]
+fn all_leaf_cvs(cvs: &JsValue, total_len: U64Arg) -> Result<Vec<ChainingValue>, JsError> {
+    let total_len = u64_arg(total_len, "total_len")?;
+    if total_len <= CHUNK_LEN as u64 {
+        return Err(JsError::new(
+            "total_len must be over 1024 bytes; hash a one-chunk input with hash()",
+        ));
+    }
+    let cvs = parse_cvs(cvs, "cvs")?;
+    let chunks = total_len.div_ceil(CHUNK_LEN as u64);
+    if cvs.len() as u64 != chunks {
+        return Err(JsError::new(&format!(
+            "cvs must hold {chunks} CVs, one per chunk of total_len"
+        )));
+    }
+    Ok(cvs)
+}
+
+// The hash of a total_len-byte input from the CVs of all its chunks, as
+// hash_chunks_batch returns them.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn root_from_leaf_cvs(
+    cvs: &JsValue,
+    total_len: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let cvs = all_leaf_cvs(cvs, total_len)?;
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("root_from_leaf_cvs", cvs.len() * OUT_LEN, || {
+        root_from_cvs(cvs, tree.mode()).as_bytes().to_vec()
+    }))
+}
+
+#[cfg(feature = "hazmat")]
+#[derive(Serialize)]
+struct Tree {
+    levels: Vec<Vec<Cv>>,
+    root: Cv,
+}
+
+// root_from_leaf_cvs that also returns the parents it merged: { levels,
+// root }, where levels[0] holds the CVs one level above the chunks, and so
+// on up to the two children of the root. Each level is an array of 32-byte
+// CVs; as in parent_cv_level, an odd CV at the end is carried up unchanged.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn build_tree(
+    cvs: &JsValue,
+    total_len: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<JsValue, JsValue> {
+    let mut level = all_leaf_cvs(cvs, total_len)?;
+    let tree = TreeMode::new(key, mode)?;
+    let built = metered("build_tree", level.len() * OUT_LEN, || {
+        let mut levels = Vec::new();
+        while level.len() > 2 {
+            level = merge_cv_level(&level, tree.mode());
+            levels.push(level.iter().copied().map(Cv).collect());
+        }
+        let root = hazmat::merge_subtrees_root(&level[0], &level[1], tree.mode());
+        Tree {
+            levels,
+            root: Cv(*root.as_bytes()),
+        }
+    });
+    to_js(&built)
+}
+
+#[cfg(feature = "hazmat")]
+fn parse_cv(cv: &[u8], name: &str) -> Result<ChainingValue, JsError> {
+    cv.try_into()
+        .map_err(|_| JsError::new(&format!("{name} must be 32 bytes")))
+}
+
+// The non-root CV of the parent of two sibling subtrees, such as two halves
+// hashed by different workers with hash_subtree.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn parent_cv(
+    left_cv: &[u8],
+    right_cv: &[u8],
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let (left, right) = (
+        parse_cv(left_cv, "left_cv")?,
+        parse_cv(right_cv, "right_cv")?,
+    );
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("parent_cv", 2 * OUT_LEN, || {
+        hazmat::merge_subtrees_non_root(&left, &right, tree.mode()).to_vec()
+    }))
+}
+
+// The hash of an input whose root splits into these two subtrees: the last
+// merge, with the root flag set.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn root_hash(
+    left_cv: &[u8],
+    right_cv: &[u8],
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let (left, right) = (
+        parse_cv(left_cv, "left_cv")?,
+        parse_cv(right_cv, "right_cv")?,
+    );
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("root_hash", 2 * OUT_LEN, || {
+        hazmat::merge_subtrees_root(&left, &right, tree.mode())
+            .as_bytes()
+            .to_vec()
+    }))
+}
+
+// root_hash for subtrees of left_len and right_len bytes, which must be the
+// canonical split of their total, as subtree_split gives it.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn root_hash_checked(
+    left_cv: &[u8],
+    right_cv: &[u8],
+    left_len: U64Arg,
+    right_len: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let (left_len, right_len) = (
+        u64_arg(left_len, "left_len")?,
+        u64_arg(right_len, "right_len")?,
+    );
+    let total = left_len
+        .checked_add(right_len)
+        .ok_or_else(|| JsError::new("left_len + right_len must not exceed 2^64 - 1"))?;
+    if total <= CHUNK_LEN as u64 {
+        return Err(JsError::new(
+            "an input of 1024 bytes or less is one chunk, not two subtrees",
+        ));
+    }
+    let expected = hazmat::left_subtree_len(total);
+    if left_len != expected {
+        return Err(JsError::new(&format!(
+            "a {total}-byte input splits at {expected} bytes, not {left_len}"
+        )));
+    }
+    root_hash(left_cv, right_cv, key, mode)
+}
+
+// root_hash with output_len bytes of extended output; the first 32 are
+// root_hash.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn root_hash_xof(
+    left_cv: &[u8],
+    right_cv: &[u8],
+    output_len: usize,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let (left, right) = (
+        parse_cv(left_cv, "left_cv")?,
+        parse_cv(right_cv, "right_cv")?,
+    );
+    let tree = TreeMode::new(key, mode)?;
+    let mut out = output_buffer(output_len)?;
+    metered("root_hash_xof", 2 * OUT_LEN, || {
+        hazmat::merge_subtrees_root_xof(&left, &right, tree.mode()).fill(&mut out)
+    });
+    Ok(out)
+}
+
+// The len XOF bytes of root_hash_xof starting at byte offset, seeking there
+// instead of generating the output before it.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn root_xof_at(
+    left_cv: &[u8],
+    right_cv: &[u8],
+    offset: U64Arg,
+    len: usize,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let (left, right) = (
+        parse_cv(left_cv, "left_cv")?,
+        parse_cv(right_cv, "right_cv")?,
+    );
+    let offset = u64_arg(offset, "offset")?;
+    let tree = TreeMode::new(key, mode)?;
+    let mut out = output_buffer(len)?;
+    metered("root_xof_at", 2 * OUT_LEN, || {
+        let mut reader = hazmat::merge_subtrees_root_xof(&left, &right, tree.mode());
+        reader.set_position(offset);
+        reader.fill(&mut out)
+    });
+    Ok(out)
+}
+
+#[cfg(feature = "hazmat")]
+#[derive(Serialize)]
+struct ChunkManifest {
+    chunks: Vec<ChunkCv>,
+    root: Cv,
+}
+
+// Per-chunk fingerprints and the root in one pass over data: { chunks, root },
+// with an { index, cv } object for each chunk, as hash_chunks_batch numbers
+// them from 0. The root is merged from the same CVs, except for inputs of
+// one chunk or less, which are hashed directly; empty data has no chunks.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn chunk_manifest(data: &[u8]) -> Result<JsValue, JsValue> {
+    let manifest = metered("chunk_manifest", data.len(), || {
+        let cvs: Vec<ChainingValue> = data
+            .chunks(CHUNK_LEN)
+            .enumerate()
+            .map(|(i, chunk)| *chunk_cv(chunk, (i * CHUNK_LEN) as u64, false).as_bytes())
+            .collect();
+        let chunks = (0..)
+            .zip(&cvs)
+            .map(|(index, cv)| ChunkCv { index, cv: Cv(*cv) });
+        ChunkManifest {
+            chunks: chunks.collect(),
+            root: Cv(if cvs.len() > 1 {
+                *root_from_cvs(cvs, Mode::Hash).as_bytes()
+            } else {
+                *blake3::hash(data).as_bytes()
+            }),
+        }
+    });
+    to_js(&manifest)
+}
+
+// Chunk CVs one at a time, over a copy of data numbered from
+// first_chunk_index. next() follows the JS iterator protocol and hashes only
+// the chunk it returns, so stopping early skips the rest of the data.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub struct ChunkCvIter {
+    data: Zeroizing<Vec<u8>>,
+    first_chunk_index: u64,
+    next_chunk: usize,
+}
+
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+impl ChunkCvIter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>, first_chunk_index: U64Arg) -> Result<ChunkCvIter, JsError> {
+        let first_chunk_index = u64_arg(first_chunk_index, "first_chunk_index")?;
+        chunk_offset(first_chunk_index, data.len(), "first_chunk_index")?;
+        Ok(ChunkCvIter {
+            data: Zeroizing::new(data),
+            first_chunk_index,
+            next_chunk: 0,
+        })
+    }
+
+    // { done: false, value: { index, cv } } for each chunk, then { done: true }.
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_cv(&mut self) -> Result<Object, JsValue> {
+        let result = Object::new();
+        let start = self.next_chunk * CHUNK_LEN;
+        let Some(rest) = self.data.get(start..).filter(|rest| !rest.is_empty()) else {
+            Reflect::set(&result, &"done".into(), &true.into())?;
+            return Ok(result);
+        };
+        let chunk = &rest[..rest.len().min(CHUNK_LEN)];
+        let index = self.first_chunk_index + self.next_chunk as u64;
+        let cv = metered("ChunkCvIter.next", chunk.len(), || {
+            chunk_cv(chunk, index * CHUNK_LEN as u64, false)
+        });
+        self.next_chunk += 1;
+        let value = to_js(&ChunkCv {
+            index,
+            cv: Cv(*cv.as_bytes()),
+        })?;
+        Reflect::set(&result, &"done".into(), &false.into())?;
+        Reflect::set(&result, &"value".into(), &value)?;
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "hazmat")]
+#[derive(Serialize)]
+struct SubtreeLeaves {
+    cv: Cv,
+    leaves: Vec<ChunkCv>,
+}
+
+// hash_subtree that also returns the CVs of the chunks it merged: { cv,
+// leaves }, with leaves as hash_chunks_batch returns them.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn hash_subtree_with_leaves(
+    data: &[u8],
+    input_offset: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<JsValue, JsValue> {
+    let input_offset = u64_arg(input_offset, "input_offset")?;
+    check_subtree(input_offset, data.len() as u64)?;
+    let tree = TreeMode::new(key, mode)?;
+    let subtree = metered("hash_subtree_with_leaves", data.len(), || {
+        let leaves: Vec<ChainingValue> = data
+            .chunks(CHUNK_LEN)
+            .enumerate()
+            .map(|(i, chunk)| tree.subtree_cv(chunk, input_offset + (i * CHUNK_LEN) as u64))
+            .collect();
+        let mut level = leaves.clone();
+        while level.len() > 1 {
+            level = merge_cv_level(&level, tree.mode());
+        }
+        let first_chunk = input_offset / CHUNK_LEN as u64;
+        SubtreeLeaves {
+            cv: Cv(level[0]),
+            leaves: (first_chunk..)
+                .zip(leaves)
+                .map(|(index, cv)| ChunkCv { index, cv: Cv(cv) })
+                .collect(),
+        }
+    });
+    to_js(&subtree)
+}
+
+#[cfg(feature = "hazmat")]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubtreeSplit {
+    left_len: u64,
+    right_len: u64,
+}
+
+// The canonical split of an input or subtree of input_len bytes: the left
+// child gets the largest power of two chunks shorter than the whole.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn subtree_split(input_len: U64Arg) -> Result<JsValue, JsValue> {
+    let input_len = u64_arg(input_len, "input_len")?;
+    if input_len <= CHUNK_LEN as u64 {
+        return Err(
+            JsError::new("input_len must be over 1024 bytes; a chunk has no children").into(),
+        );
+    }
+    let left_len = hazmat::left_subtree_len(input_len);
+    to_js(&SubtreeSplit {
+        left_len,
+        right_len: input_len - left_len,
+    })
+}
+
+#[cfg(feature = "hazmat")]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubtreeSpan {
+    input_offset: u64,
+    length: u64,
+}
+
+#[cfg(feature = "hazmat")]
+fn push_subtree_spans(offset: u64, len: u64, depth: u32, spans: &mut Vec<SubtreeSpan>) {
+    if depth == 0 || len <= CHUNK_LEN as u64 {
+        spans.push(SubtreeSpan {
+            input_offset: offset,
+            length: len,
+        });
+    } else {
+        let left_len = hazmat::left_subtree_len(len);
+        push_subtree_spans(offset, left_len, depth - 1, spans);
+        push_subtree_spans(offset + left_len, len - left_len, depth - 1, spans);
+    }
+}
+
+// The subtrees max_depth levels below the root of an input_len-byte input,
+// left to right, as { inputOffset, length }. A branch that reaches a single
+// chunk stops there, so there are at most 2^max_depth spans.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn subtree_spans(input_len: U64Arg, max_depth: u32) -> Result<JsValue, JsValue> {
+    let input_len = u64_arg(input_len, "input_len")?;
+    let limit = 1u64.checked_shl(max_depth).unwrap_or(u64::MAX);
+    let count = input_len.div_ceil(CHUNK_LEN as u64).clamp(1, limit);
+    check_alloc(count.saturating_mul(size_of::<SubtreeSpan>() as u64))?;
+    let mut spans = Vec::with_capacity(count as usize);
+    push_subtree_spans(0, input_len, max_depth, &mut spans);
+    to_js(&spans)
+}
+
+// Checks that len bytes at offset can be hashed as one non-root subtree.
+// Subtrees start on a chunk boundary and are at most max_subtree_len long.
+// At offset 0 a non-root subtree is a left child, so a whole power of two
+// chunks; any other length there spans the whole input, which has a root
+// hash rather than a CV.
+#[cfg(feature = "hazmat")]
+fn check_subtree(offset: u64, len: u64) -> Result<(), JsError> {
+    if len == 0 {
+        return Err(JsError::new("a subtree must not be empty"));
+    }
+    if !offset.is_multiple_of(CHUNK_LEN as u64) {
+        return Err(JsError::new(&format!(
+            "input_offset {offset} is not a multiple of 1024"
+        )));
+    }
+    if offset.checked_add(len).is_none() {
+        return Err(JsError::new("input_offset + len must not exceed 2^64 - 1"));
+    }
+    match hazmat::max_subtree_len(offset) {
+        Some(max) if len > max => Err(JsError::new(&format!(
+            "a subtree at input_offset {offset} holds at most {max} bytes"
+        ))),
+        None if len < CHUNK_LEN as u64 || !len.is_power_of_two() => Err(JsError::new(
+            "a subtree at input_offset 0 must be a power of two chunks; hash a whole input with hash()",
+        )),
+        _ => Ok(()),
+    }
+}
+
+// The longest subtree that may start at input_offset, or undefined at offset
+// 0, where a subtree may be any power of two chunks.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn max_subtree_len(input_offset: U64Arg) -> Result<Option<f64>, JsError> {
+    let input_offset = u64_arg(input_offset, "input_offset")?;
+    if !input_offset.is_multiple_of(CHUNK_LEN as u64) {
+        return Err(JsError::new(&format!(
+            "input_offset {input_offset} is not a multiple of 1024"
+        )));
+    }
+    hazmat::max_subtree_len(input_offset)
+        .map(|max| js_number(max.into(), "max_subtree_len"))
+        .transpose()
+}
+
+// Whether len bytes at offset form a subtree hash_subtree accepts.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn is_valid_subtree(offset: U64Arg, len: U64Arg) -> Result<bool, JsError> {
+    let (offset, len) = (u64_arg(offset, "offset")?, u64_arg(len, "len")?);
+    Ok(check_subtree(offset, len).is_ok())
+}
+
+// The non-root CV of the subtree holding data, which starts input_offset
+// bytes into the input. Offsets and lengths that don't form a canonical
+// subtree throw instead of giving a CV that no tree contains.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn hash_subtree(
+    data: &[u8],
+    input_offset: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let input_offset = u64_arg(input_offset, "input_offset")?;
+    check_subtree(input_offset, data.len() as u64)?;
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("hash_subtree", data.len(), || {
+        tree.subtree_cv(data, input_offset).to_vec()
+    }))
+}
+
+// hash_subtree for a subtree starting at chunk start_chunk_index.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn hash_subtree_at_chunk(
+    data: &[u8],
+    start_chunk_index: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let start_chunk_index = u64_arg(start_chunk_index, "start_chunk_index")?;
+    let input_offset = chunk_offset(start_chunk_index, data.len(), "start_chunk_index")?;
+    check_subtree(input_offset, data.len() as u64)?;
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("hash_subtree_at_chunk", data.len(), || {
+        tree.subtree_cv(data, input_offset).to_vec()
+    }))
+}
+
+// hash_subtree over segments taken as one contiguous subtree, copied in
+// block by block as in hash_gather, so a subtree that arrived as several
+// buffers is not joined in JS first.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn hash_subtree_gather(
+    parts: &Array,
+    input_offset: U64Arg,
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<Vec<u8>, JsError> {
+    let input_offset = u64_arg(input_offset, "input_offset")?;
+    let parts = parts
+        .iter()
+        .map(|part| part.dyn_into())
+        .collect::<Result<Vec<Uint8Array>, _>>()
+        .map_err(|_| JsError::new("parts must be Uint8Array"))?;
+    let len = parts.iter().map(|part| u64::from(part.length())).sum();
+    check_subtree(input_offset, len)?;
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("hash_subtree_gather", len as usize, || {
+        let mut hasher = tree.hasher();
+        hasher.set_input_offset(input_offset);
+        for part in &parts {
+            update_from_array(&mut hasher, part);
+        }
+        hasher.finalize_non_root().to_vec()
+    }))
+}
+
+// Checks data against the CV hash_subtree would return for it, comparing
+// in constant time inside wasm, so the computed CV never reaches JS.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn verify_subtree(
+    data: &[u8],
+    input_offset: U64Arg,
+    expected_cv: &[u8],
+    key: &JsValue,
+    mode: Option<HashMode>,
+) -> Result<bool, JsError> {
+    let input_offset = u64_arg(input_offset, "input_offset")?;
+    check_subtree(input_offset, data.len() as u64)?;
+    let expected = parse_cv(expected_cv, "expected_cv")?;
+    let tree = TreeMode::new(key, mode)?;
+    Ok(metered("verify_subtree", data.len(), || {
+        blake3::Hash::from(tree.subtree_cv(data, input_offset)) == expected
+    }))
+}
+
+// The CV of len zero bytes at offset, for sparse or preallocated regions.
+// A chunk's CV depends on its index, so zero subtrees at different offsets
+// share no CVs and the zeros are hashed here, from a static block instead
+// of a caller buffer.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+pub fn zero_subtree_cv(offset: U64Arg, len: U64Arg) -> Result<Vec<u8>, JsError> {
+    static ZEROS: [u8; 16 * CHUNK_LEN] = [0; 16 * CHUNK_LEN];
+    let (offset, len) = (u64_arg(offset, "offset")?, u64_arg(len, "len")?);
+    check_subtree(offset, len)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.set_input_offset(offset);
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(ZEROS.len() as u64);
+        hasher.update(&ZEROS[..n as usize]);
+        remaining -= n;
+    }
+    Ok(hasher.finalize_non_root().to_vec())
+}
+
+// Joins chunk and subtree CVs, pushed left to right, into the root hash by
+// the tree's left-leaning rule. When more input arrives after two equal
+// subtrees on top of the stack, they are siblings and merge into their
+// parent; finalize_root merges what is left from the right, the last merge
+// as the root.
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct CvStack {
+    tree: TreeMode,
+    // The CVs of complete subtrees and their sizes in chunks, largest first.
+    stack: Vec<(ChainingValue, u64)>,
+    chunks: u64,
+    // The length of the input, once a subtree that isn't a whole power of
+    // two chunks has been pushed; nothing may follow it.
+    end: Option<u64>,
+}
+
+#[cfg(feature = "hazmat")]
+impl CvStack {
+    fn check_next(&self, chunk_index: u64) -> Result<(), JsError> {
+        if let Some(end) = self.end {
+            return Err(JsError::new(&format!(
+                "the input ended at {end} bytes with a partial subtree"
+            )));
+        }
+        if chunk_index != self.chunks {
+            return Err(JsError::new(&format!(
+                "the next CV must start at chunk {}, not {chunk_index}",
+                self.chunks
+            )));
+        }
+        Ok(())
+    }
+
+    fn push_span(
+        &mut self,
+        name: &'static str,
+        cv: ChainingValue,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), JsError> {
+        check_subtree(offset, len)?;
+        let chunks = len.div_ceil(CHUNK_LEN as u64);
+        metered(name, OUT_LEN, || self.push(cv, chunks));
+        if !len.is_multiple_of(CHUNK_LEN as u64) || !chunks.is_power_of_two() {
+            self.end = Some(offset + len);
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, cv: ChainingValue, chunks: u64) {
+        while let [.., (left, left_chunks), (right, right_chunks)] = self.stack[..] {
+            if left_chunks != right_chunks {
+                break;
+            }
+            self.stack.truncate(self.stack.len() - 2);
+            let parent = hazmat::merge_subtrees_non_root(&left, &right, self.tree.mode());
+            self.stack.push((parent, 2 * left_chunks));
+        }
+        self.stack.push((cv, chunks));
+        self.chunks += chunks;
+    }
+
+    // Merges the stack, of two or more CVs, from the right.
+    fn root(&self) -> blake3::Hash {
+        let mut cvs = self.stack.iter().rev().map(|&(cv, _)| cv);
+        let mut right = cvs.next().unwrap();
+        let mut left = cvs.next().unwrap();
+        for next in cvs {
+            right = hazmat::merge_subtrees_non_root(&left, &right, self.tree.mode());
+            left = next;
+        }
+        hazmat::merge_subtrees_root(&left, &right, self.tree.mode())
+    }
+}
+
+// Keyed stacks hold a key and keyed CVs.
+#[cfg(feature = "hazmat")]
+impl Drop for CvStack {
+    fn drop(&mut self) {
+        for (cv, _) in &mut self.stack {
+            cv.zeroize();
+        }
+    }
+}
+
+#[cfg(feature = "hazmat")]
+#[wasm_bindgen]
+impl CvStack {
+    // The optional key and mode are those of the tree exports, so a keyed
+    // or derive_key tree is merged as they hash it.
+    #[wasm_bindgen(constructor)]
+    pub fn new(key: &JsValue, mode: Option<HashMode>) -> Result<CvStack, JsError> {
+        let mut stack = CvStack::default();
+        stack.tree = TreeMode::new(key, mode)?;
+        Ok(stack)
+    }
+
+    // A chunk's CV as hash_chunks_batch returns it; the last chunk may be
+    // short, so its length is only checked by finalize_root.
+    pub fn push_chunk_cv(&mut self, cv: &[u8], chunk_index: U64Arg) -> Result<(), JsError> {
+        let cv = parse_cv(cv, "cv")?;
+        let chunk_index = u64_arg(chunk_index, "chunk_index")?;
+        chunk_offset(chunk_index, CHUNK_LEN, "chunk_index")?;
+        self.check_next(chunk_index)?;
+        metered("CvStack.push_chunk_cv", OUT_LEN, || self.push(cv, 1));
+        Ok(())
+    }
+
+    // A subtree's CV as hash_subtree returns it, for the len bytes at offset.
+    pub fn push_subtree_cv(
+        &mut self,
+        cv: &[u8],
+        offset: U64Arg,
+        len: U64Arg,
+    ) -> Result<(), JsError> {
+        let cv = parse_cv(cv, "cv")?;
+        let (offset, len) = (u64_arg(offset, "offset")?, u64_arg(len, "len")?);
+        self.check_next(offset / CHUNK_LEN as u64)?;
+        self.push_span("CvStack.push_subtree_cv", cv, offset, len)
+    }
+
+    // push_subtree_cv at the end of what was pushed so far, for subtrees
+    // that arrive in input order with only their lengths.
+    pub fn push_subtree(&mut self, cv: &[u8], len: U64Arg) -> Result<(), JsError> {
+        let cv = parse_cv(cv, "cv")?;
+        let len = u64_arg(len, "len")?;
+        self.check_next(self.chunks)?;
+        self.push_span(
+            "CvStack.push_subtree",
+            cv,
+            self.chunks * CHUNK_LEN as u64,
+            len,
+        )
+    }
+
+    // The hash of the input the pushed CVs cover. total_len, if given, must
+    // match them. The stack is left as it was, so more CVs may be pushed.
+    pub fn finalize_root(&self, total_len: Option<U64Arg>) -> Result<Vec<u8>, JsError> {
+        if let Some(total_len) = total_len {
+            let total_len = u64_arg(total_len, "total_len")?;
+            let covered = match self.end {
+                Some(end) => total_len == end,
+                None => total_len.div_ceil(CHUNK_LEN as u64) == self.chunks,
+            };
+            if !covered {
+                return Err(JsError::new(&format!(
+                    "total_len {total_len} does not match the {} chunks pushed",
+                    self.chunks
+                )));
+            }
+        }
+        if self.stack.len() < 2 {
+            return Err(JsError::new(
+                "finalize_root needs the CVs of at least two subtrees; hash a one-chunk input with hash()",
+            ));
+        }
+        let root = metered("CvStack.finalize_root", self.stack.len() * OUT_LEN, || {
+            self.root()
+        });
+        Ok(root.as_bytes().to_vec())
+    }
+}
+
+// Paths are relative, "/"-separated and already normalized by the caller;
+// anything that could name the same file two ways is rejected.
+fn check_manifest_path(path: &str) -> Result<(), JsError> {
+    let bad = path.is_empty()
+        || path.contains('\\')
+        || path.contains('\0')
+        || path
+            .split('/')
+            .any(|segment| matches!(segment, "" | "." | ".."));
+    if bad {
+        return Err(JsError::new(&format!("invalid manifest path: {path:?}")));
+    }
+    Ok(())
+}
+
+// A canonical listing of files by path, root hash and length. The encoding
+// is MANIFEST_MAGIC, the entry count, then for each entry in byte order of
+// path: path length, path, file length (all lengths u64 little-endian) and
+// the 32-byte root. The manifest root is the hash of that encoding.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Manifest {
+    entries: BTreeMap<String, (blake3::Hash, u64)>,
+}
+
+#[wasm_bindgen]
+impl Manifest {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Manifest {
+        Manifest::default()
+    }
+
+    pub fn add(&mut self, path: String, root: &[u8], file_len: U64Arg) -> Result<(), JsError> {
+        check_manifest_path(&path)?;
+        let entry = (parse_hash(root)?, u64_arg(file_len, "file_len")?);
+        if self.entries.contains_key(&path) {
+            return Err(JsError::new(&format!("duplicate manifest path: {path:?}")));
+        }
+        self.entries.insert(path, entry);
+        Ok(())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = MANIFEST_MAGIC.to_vec();
+        encoded.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+        for (path, (root, file_len)) in &self.entries {
+            encoded.extend_from_slice(&(path.len() as u64).to_le_bytes());
+            encoded.extend_from_slice(path.as_bytes());
+            encoded.extend_from_slice(&file_len.to_le_bytes());
+            encoded.extend_from_slice(root.as_bytes());
+        }
+        encoded
+    }
+
+    pub fn root(&self) -> Vec<u8> {
+        let encoded = self.encode();
+        metered("Manifest.root", encoded.len(), || {
+            blake3::hash(&encoded).as_bytes().to_vec()
+        })
+    }
+}
+
+#[wasm_bindgen]
+pub fn hash_xof(input: &[u8], output_len: usize) -> Result<Vec<u8>, JsError> {
+    let mut out = output_buffer(output_len)?;
+    metered("hash_xof", input.len(), || {
+        blake3::Hasher::new()
+            .update(input)
+            .finalize_xof()
+            .fill(&mut out)
+    });
+    Ok(out)
+}
+
+#[wasm_bindgen]
+pub fn keyed_hash(key: &[u8], input: &[u8]) -> Result<Vec<u8>, JsError> {
+    let key = key_array(key)?;
+    Ok(metered("keyed_hash", input.len(), || {
+        blake3::keyed_hash(key, input).as_bytes().to_vec()
+    }))
+}
+
+// Feeds each block to every hasher while it is still in cache, so the input
+// is read from memory once however many digests are taken.
+#[wasm_bindgen]
+pub fn multi_digest(input: &[u8], keys: &Array) -> Result<Vec<u8>, JsError> {
+    // Zeroizing so the keyed hashers built so far are wiped when a later
+    // key fails validation and the early return drops them.
+    let mut hashers = Zeroizing::new(vec![blake3::Hasher::new()]);
+    for key in keys.iter() {
+        let key: Uint8Array = key
+            .dyn_into()
+            .map_err(|_| JsError::new("keys must be Uint8Array"))?;
+        if key.length() as usize != blake3::KEY_LEN {
+            return Err(JsError::new("key must be 32 bytes"));
+        }
+        let mut key_bytes = Zeroizing::new([0; blake3::KEY_LEN]);
+        key.copy_to(&mut key_bytes[..]);
+        hashers.push(blake3::Hasher::new_keyed(&key_bytes));
+    }
+    Ok(multi_digest_with("multi_digest", input, hashers))
+}
+
+fn multi_digest_with(
+    name: &'static str,
+    input: &[u8],
+    mut hashers: Zeroizing<Vec<blake3::Hasher>>,
+) -> Vec<u8> {
+    metered(name, input.len(), || {
+        for block in input.chunks(MULTI_DIGEST_BLOCK_LEN) {
+            for hasher in hashers.iter_mut() {
+                hasher.update(block);
+            }
+        }
+    });
+    hashers
+        .iter()
+        .flat_map(|hasher| *hasher.finalize().as_bytes())
+        .collect()
+}
+
+// FastCDC 2020 over the whole buffer. fastcdc asserts on out-of-range
+// sizes, so they are checked here first to throw instead of trapping.
+#[cfg(feature = "cdc")]
+fn cdc_chunker(data: &[u8], min: u32, avg: u32, max: u32) -> Result<FastCDC<'_>, JsError> {
+    use fastcdc::v2020::{
+        AVERAGE_MAX, AVERAGE_MIN, MAXIMUM_MAX, MAXIMUM_MIN, MINIMUM_MAX, MINIMUM_MIN,
+    };
+    if !(MINIMUM_MIN..=MINIMUM_MAX).contains(&min)
+        || !(AVERAGE_MIN..=AVERAGE_MAX).contains(&avg)
+        || !(MAXIMUM_MIN..=MAXIMUM_MAX).contains(&max)
+        || min > avg
+        || avg > max
+    {
+        return Err(JsError::new(&format!(
+            "chunk sizes must satisfy min <= avg <= max with min in \
+             {MINIMUM_MIN}..={MINIMUM_MAX}, avg in {AVERAGE_MIN}..={AVERAGE_MAX} \
+             and max in {MAXIMUM_MIN}..={MAXIMUM_MAX}"
+        )));
+    }
+    Ok(FastCDC::new(data, min, avg, max))
+}
+
+// End offset of each content-defined chunk; the last is data.length.
+#[cfg(feature = "cdc")]
+#[wasm_bindgen]
+pub fn cdc_chunk_boundaries(
+    data: &[u8],
+    min: u32,
+    avg: u32,
+    max: u32,
+) -> Result<Vec<u32>, JsError> {
+    Ok(cdc_chunker(data, min, avg, max)?
+        .map(|chunk| (chunk.offset + chunk.length) as u32)
+        .collect())
+}
+
+#[cfg(feature = "cdc")]
+#[wasm_bindgen]
+pub fn cdc_chunk_and_hash(data: &[u8], min: u32, avg: u32, max: u32) -> Result<Array, JsValue> {
+    let chunker = cdc_chunker(data, min, avg, max)?;
+    metered("cdc_chunk_and_hash", data.len(), || {
+        let chunks = Array::new();
+        for chunk in chunker {
+            let digest = blake3::hash(&data[chunk.offset..chunk.offset + chunk.length]);
+            let entry = Object::new();
+            Reflect::set(&entry, &"offset".into(), &(chunk.offset as u32).into())?;
+            Reflect::set(&entry, &"length".into(), &(chunk.length as u32).into())?;
+            Reflect::set(
+                &entry,
+                &"hash".into(),
+                &Uint8Array::from(&digest.as_bytes()[..]),
+            )?;
+            chunks.push(&entry);
+        }
+        Ok(chunks)
+    })
+}
+
+#[wasm_bindgen]
+pub fn verify_keyed(key: &[u8], input: &[u8], expected_tag: &[u8]) -> Result<bool, JsError> {
+    let expected = parse_hash(expected_tag)?;
+    let key = key_array(key)?;
+    Ok(metered("verify_keyed", input.len(), || {
+        blake3::keyed_hash(key, input) == expected
+    }))
+}
+
+#[wasm_bindgen]
+pub fn derive_key(context: &str, key_material: &[u8]) -> Vec<u8> {
+    metered("derive_key", key_material.len(), || {
+        blake3::derive_key(context, key_material).to_vec()
+    })
+}
+
+// The 32-byte key derive_key hashes its context string into, for the tree
+// exports' HashMode.DeriveKeyMaterial: their roots are then derive_key of
+// the material under context.
+#[wasm_bindgen]
+pub fn derive_key_context_key(context: &str) -> Vec<u8> {
+    metered("derive_key_context_key", context.len(), || {
+        hazmat::hash_derive_key_context(context).to_vec()
+    })
+}
+
+#[wasm_bindgen]
+pub fn derive_key_expand(
+    context: &str,
+    key_material: &[u8],
+    output_len: usize,
+) -> Result<Vec<u8>, JsError> {
+    let mut out = output_buffer(output_len)?;
+    metered("derive_key_expand", key_material.len(), || {
+        blake3::Hasher::new_derive_key(context)
+            .update(key_material)
+            .finalize_xof()
+            .fill(&mut out)
+    });
+    Ok(out)
+}
+
+// For contexts that are bytes rather than a hardcoded string. The context
+// material is first reduced to a key under a fixed derive_key context, which
+// then keys the hash of the key material. Changing this context string
+// changes every output, so it is versioned.
+#[wasm_bindgen]
+pub fn derive_key_from_material(context_material: &[u8], key_material: &[u8]) -> Vec<u8> {
+    metered(
+        "derive_key_from_material",
+        context_material.len() + key_material.len(),
+        || {
+            let context_key = blake3::derive_key(
+                "blake3-wasm 2026-10-14 derive_key_from_material v1",
+                context_material,
+            );
+            blake3::keyed_hash(&context_key, key_material)
+                .as_bytes()
+                .to_vec()
+        },
+    )
+}
+
+// Per-chunk keys for chunk stores: derive_key_expand over the master key and
+// the chunk index (little-endian u64) under a fixed, versioned context, so
+// every implementation of the store derives the same keys.
+#[wasm_bindgen]
+pub fn derive_chunk_key(
+    master_key: &[u8],
+    chunk_index: U64Arg,
+    output_len: usize,
+) -> Result<Vec<u8>, JsError> {
+    derive_chunk_key_with(
+        "derive_chunk_key",
+        key_array(master_key)?,
+        chunk_index,
+        output_len,
+    )
+}
+
+fn derive_chunk_key_with(
+    name: &'static str,
+    master_key: &[u8; blake3::KEY_LEN],
+    chunk_index: U64Arg,
+    output_len: usize,
+) -> Result<Vec<u8>, JsError> {
+    let chunk_index = u64_arg(chunk_index, "chunk_index")?;
+    let mut out = output_buffer(output_len)?;
+    metered(name, master_key.len() + 8, || {
+        blake3::Hasher::new_derive_key("blake3-wasm 2026-10-14 derive_chunk_key v1")
+            .update(master_key)
+            .update(&chunk_index.to_le_bytes())
+            .finalize_xof()
+            .fill(&mut out)
+    });
+    Ok(out)
+}
+
+#[wasm_bindgen]
+pub fn simd_enabled() -> bool {
+    cfg!(target_feature = "simd128")
+}
+
+// Linear memory never shrinks, so memory_pages is the high-water mark of the
+// whole module; heap_bytes is what Rust currently holds within it.
+#[wasm_bindgen]
+pub fn memory_stats() -> Result<Object, JsValue> {
+    #[cfg(target_arch = "wasm32")]
+    let pages = core::arch::wasm32::memory_size(0);
+    #[cfg(not(target_arch = "wasm32"))]
+    let pages = 0;
+    let stats = Object::new();
+    Reflect::set(&stats, &"memory_pages".into(), &(pages as u32).into())?;
+    Reflect::set(
+        &stats,
+        &"memory_bytes".into(),
+        &(pages as f64 * 65536.0).into(),
+    )?;
+    let heap_bytes = HEAP_BYTES.load(Ordering::Relaxed) as f64;
+    Reflect::set(&stats, &"heap_bytes".into(), &heap_bytes.into())?;
+    let peak_heap_bytes = PEAK_HEAP_BYTES.load(Ordering::Relaxed) as f64;
+    Reflect::set(&stats, &"peak_heap_bytes".into(), &peak_heap_bytes.into())?;
+    Ok(stats)
+}
+
+// Grows the heap by allocating and freeing a block of this size, so the
+// allocator keeps the pages and later hashing doesn't call memory.grow (which
+// detaches Uint8Array views onto memory) until it outgrows them. Returns the
+// resulting linear memory size in bytes.
+#[wasm_bindgen]
+pub fn reserve_memory(bytes: usize) -> Result<f64, JsError> {
+    check_alloc(bytes as u64)?;
+    let mut block = Vec::<u8>::new();
+    block
+        .try_reserve_exact(bytes)
+        .map_err(|_| JsError::new(&format!("cannot allocate {bytes} bytes")))?;
+    drop(block);
+    #[cfg(target_arch = "wasm32")]
+    let pages = core::arch::wasm32::memory_size(0);
+    #[cfg(not(target_arch = "wasm32"))]
+    let pages = 0;
+    Ok(pages as f64 * 65536.0)
+}
+
+// Build facts a loader can use to pick between bundles without probing for
+// exports.
+#[wasm_bindgen]
+pub fn capabilities() -> Result<Object, JsValue> {
+    let features = Array::new();
+    for (name, enabled) in [
+        ("bao", cfg!(feature = "bao")),
+        ("streams", cfg!(feature = "streams")),
+        ("hazmat", cfg!(feature = "hazmat")),
+        ("node", cfg!(feature = "node")),
+        ("cdc", cfg!(feature = "cdc")),
+        ("small-alloc", cfg!(feature = "small-alloc")),
+    ] {
+        if enabled {
+            features.push(&name.into());
+        }
+    }
+    let caps = Object::new();
+    Reflect::set(&caps, &"simd".into(), &simd_enabled().into())?;
+    Reflect::set(&caps, &"threads".into(), &false.into())?;
+    Reflect::set(&caps, &"features".into(), &features)?;
+    Reflect::set(&caps, &"blake3_version".into(), &BLAKE3_VERSION.into())?;
+    Reflect::set(&caps, &"chunk_len".into(), &(CHUNK_LEN as u32).into())?;
+    Ok(caps)
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmHasher {
+    inner: blake3::Hasher,
+    // Where the subtree starts, once set_input_offset is called, and the
+    // hasher as it was before, for reset: blake3's reset keeps the offset.
+    input_offset: Option<u64>,
+    before_offset: Option<blake3::Hasher>,
+}
+
+impl From<blake3::Hasher> for WasmHasher {
+    fn from(inner: blake3::Hasher) -> WasmHasher {
+        WasmHasher {
+            inner,
+            input_offset: None,
+            before_offset: None,
+        }
+    }
+}
+
+// The subtree methods check their arguments here first, since blake3's
+// hazmat asserts would otherwise panic on misuse.
+impl WasmHasher {
+    fn check_update(&self, len: usize) -> Result<(), JsError> {
+        let Some(offset) = self.input_offset else {
+            return Ok(());
+        };
+        match hazmat::max_subtree_len(offset) {
+            Some(max) if self.inner.count() + len as u64 > max => Err(JsError::new(&format!(
+                "the subtree at input_offset {offset} holds at most {max} bytes"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // A subtree hasher has no root hash, so only finalize_non_root applies.
+    fn root_hasher(&self) -> Result<&blake3::Hasher, JsError> {
+        match self.input_offset {
+            Some(offset) if offset > 0 => Err(JsError::new(
+                "a hasher with a non-zero input_offset has no root; use finalize_non_root",
+            )),
+            _ => Ok(&self.inner),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmHasher {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmHasher {
+        WasmHasher::default()
+    }
+
+    pub fn update(&mut self, input: &[u8]) -> Result<(), JsError> {
+        self.check_update(input.len())?;
+        metered("WasmHasher.update", input.len(), || {
+            self.inner.update(input);
+        });
+        Ok(())
+    }
+
+    pub fn update_array(&mut self, input: &Uint8Array) -> Result<(), JsError> {
+        self.check_update(input.length() as usize)?;
+        metered("WasmHasher.update_array", input.length() as usize, || {
+            update_from_array(&mut self.inner, input)
+        });
+        Ok(())
+    }
+
+    pub fn update_memory(
+        &mut self,
+        memory: &WebAssembly::Memory,
+        offset: u32,
+        len: u32,
+    ) -> Result<(), JsError> {
+        let input = memory_view(memory, offset, len)?;
+        self.check_update(len as usize)?;
+        metered("WasmHasher.update_memory", len as usize, || {
+            update_from_array(&mut self.inner, &input)
+        });
+        Ok(())
+    }
+
+    // Makes this hasher hash the subtree starting offset bytes into the
+    // input, as hash_subtree does, so a worker can stream a subtree in
+    // pieces. Must come before any input.
+    #[cfg(feature = "hazmat")]
+    pub fn set_input_offset(&mut self, offset: U64Arg) -> Result<(), JsError> {
+        let offset = u64_arg(offset, "offset")?;
+        if self.inner.count() > 0 {
+            return Err(JsError::new("set_input_offset must come before any input"));
+        }
+        if !offset.is_multiple_of(CHUNK_LEN as u64) {
+            return Err(JsError::new(&format!(
+                "input_offset {offset} is not a multiple of 1024"
+            )));
+        }
+        self.before_offset.get_or_insert_with(|| self.inner.clone());
+        self.inner.set_input_offset(offset);
+        self.input_offset = Some(offset);
+        Ok(())
+    }
+
+    // The subtree's CV, as hash_subtree returns it; throws when the input
+    // so far is not a whole subtree at the offset (0 if none was set).
+    #[cfg(feature = "hazmat")]
+    pub fn finalize_non_root(&self) -> Result<Vec<u8>, JsError> {
+        check_subtree(self.input_offset.unwrap_or(0), self.inner.count())?;
+        Ok(self.inner.finalize_non_root().to_vec())
+    }
+
+    pub fn finalize(&self) -> Result<Vec<u8>, JsError> {
+        Ok(self.root_hasher()?.finalize().as_bytes().to_vec())
+    }
+
+    pub fn finalize_xof(&self) -> Result<OutputReader, JsError> {
+        Ok(OutputReader {
+            inner: self.root_hasher()?.finalize_xof(),
+        })
+    }
+
+    // Also clears the input offset.
+    pub fn reset(&mut self) {
+        match self.before_offset.take() {
+            Some(hasher) => {
+                self.inner.zeroize();
+                self.inner = hasher;
+            }
+            None => {
+                self.inner.reset();
+            }
+        }
+        self.input_offset = None;
+    }
+}
+
+// Hashers may hold a key or keyed input state, so they wipe themselves when
+// freed; so do the other classes below that carry hash state.
+impl Drop for WasmHasher {
+    fn drop(&mut self) {
+        self.inner.zeroize();
+        self.before_offset.zeroize();
+    }
+}
+
+// Usable both as a WritableStream underlying sink (write/close) and as a
+// TransformStream transformer that passes chunks through (transform/flush).
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct HashingSink {
+    hasher: blake3::Hasher,
+    digest: Option<blake3::Hash>,
+}
+
+#[cfg(feature = "streams")]
+#[wasm_bindgen]
+impl HashingSink {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HashingSink {
+        HashingSink::default()
+    }
+
+    pub fn write(&mut self, chunk: JsValue) -> Result<(), JsError> {
+        update_from_array(&mut self.hasher, &byte_chunk(chunk)?);
+        Ok(())
+    }
+
+    pub fn close(&mut self) {
+        self.digest = Some(self.hasher.finalize());
+    }
+
+    pub fn transform(
+        &mut self,
+        chunk: JsValue,
+        controller: &TransformStreamDefaultController,
+    ) -> Result<(), JsValue> {
+        let chunk = byte_chunk(chunk)?;
+        update_from_array(&mut self.hasher, &chunk);
+        controller.enqueue_with_chunk(&chunk)
+    }
+
+    pub fn flush(&mut self) {
+        self.close();
+    }
+
+    pub fn digest(&self) -> Result<Vec<u8>, JsError> {
+        self.digest
+            .map(|digest| digest.as_bytes().to_vec())
+            .ok_or_else(|| JsError::new("stream has not closed yet"))
+    }
+}
+
+#[cfg(feature = "streams")]
+impl Drop for HashingSink {
+    fn drop(&mut self) {
+        self.hasher.zeroize();
+    }
+}
+
+// Bytes of an ArrayBuffer or any ArrayBuffer view (typed array, DataView,
+// Node Buffer), without copying.
+fn buffer_source(data: &JsValue) -> Result<Uint8Array, JsError> {
+    if data.is_instance_of::<ArrayBuffer>() {
+        return Ok(Uint8Array::new(data));
+    }
+    if !ArrayBuffer::is_view(data) {
+        return Err(JsError::new(
+            "data must be an ArrayBuffer or ArrayBuffer view",
+        ));
+    }
+    let property = |name: &str| Reflect::get(data, &name.into()).unwrap_or_default();
+    Ok(Uint8Array::new_with_byte_offset_and_length(
+        &property("buffer"),
+        property("byteOffset").as_f64().unwrap_or_default() as u32,
+        property("byteLength").as_f64().unwrap_or_default() as u32,
+    ))
+}
+
+// SubtleCrypto.digest() shape: resolves to an ArrayBuffer, rejects on bad
+// input rather than throwing.
+#[wasm_bindgen]
+pub fn digest(data: &JsValue) -> Promise {
+    match buffer_source(data) {
+        Ok(bytes) => {
+            let digest = metered("digest", bytes.length() as usize, || {
+                let mut hasher = blake3::Hasher::new();
+                update_from_array(&mut hasher, &bytes);
+                hasher.finalize()
+            });
+            let digest: JsValue = Uint8Array::from(&digest.as_bytes()[..]).buffer().into();
+            Promise::resolve(&digest)
+        }
+        Err(e) => Promise::reject(&e.into()),
+    }
+}
+
+// Node crypto Hash shape. update() returns another handle on the same state,
+// so calls chain as they do in Node; digest() may only be called once.
+#[wasm_bindgen(js_name = Hash)]
+pub struct NodeHash {
+    hasher: Rc<RefCell<Option<blake3::Hasher>>>,
+}
+
+#[wasm_bindgen(js_name = createHash)]
+pub fn create_hash(algorithm: Option<String>) -> Result<NodeHash, JsError> {
+    if algorithm.is_some_and(|name| !name.eq_ignore_ascii_case("blake3")) {
+        return Err(JsError::new("only blake3 is supported"));
+    }
+    Ok(NodeHash::from(blake3::Hasher::new()))
+}
+
+impl From<blake3::Hasher> for NodeHash {
+    fn from(hasher: blake3::Hasher) -> NodeHash {
+        NodeHash {
+            hasher: Rc::new(RefCell::new(Some(hasher))),
+        }
+    }
+}
+
+// Input that may be a string, hashed as UTF-8, or an ArrayBuffer or view.
+enum JsData {
+    Text(String),
+    Bytes(Uint8Array),
+}
+
+impl JsData {
+    fn new(data: &JsValue) -> Result<JsData, JsError> {
+        Ok(match data.as_string() {
+            Some(text) => JsData::Text(text),
+            None => JsData::Bytes(buffer_source(data)?),
+        })
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            JsData::Text(text) => text.len(),
+            JsData::Bytes(bytes) => bytes.length() as usize,
+        }
+    }
+
+    fn update(&self, hasher: &mut blake3::Hasher) {
+        match self {
+            JsData::Text(text) => {
+                hasher.update(text.as_bytes());
+            }
+            JsData::Bytes(bytes) => update_from_array(hasher, bytes),
+        }
+    }
+}
+
+// The { length } options of the blake3 npm package; 32 bytes by default.
+fn length_option(options: &JsValue) -> Result<usize, JsError> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(OUT_LEN);
+    }
+    let length = Reflect::get(options, &"length".into())
+        .map_err(|_| JsError::new("options must be an object"))?;
+    if length.is_undefined() {
+        return Ok(OUT_LEN);
+    }
+    match length.as_f64() {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => Ok(n as usize),
+        _ => Err(JsError::new("length must be a non-negative integer")),
+    }
+}
+
+fn fill_output(hasher: &blake3::Hasher, len: usize) -> Result<Vec<u8>, JsError> {
+    let mut out = output_buffer(len)?;
+    hasher.finalize_xof().fill(&mut out);
+    Ok(out)
+}
+
+// keyedHash, deriveKey, createKeyed and createDeriveKey follow the blake3 npm
+// package, alongside createHash.
+#[wasm_bindgen(js_name = keyedHash)]
+pub fn npm_keyed_hash(key: &[u8], data: &JsValue, options: &JsValue) -> Result<Vec<u8>, JsError> {
+    let mut hasher = blake3::Hasher::new_keyed(key_array(key)?);
+    let (data, len) = (JsData::new(data)?, length_option(options)?);
+    metered("keyedHash", data.len(), || {
+        data.update(&mut hasher);
+        fill_output(&hasher, len)
+    })
+}
+
+#[wasm_bindgen(js_name = deriveKey)]
+pub fn npm_derive_key(
+    context: &str,
+    material: &JsValue,
+    options: &JsValue,
+) -> Result<Vec<u8>, JsError> {
+    let mut hasher = blake3::Hasher::new_derive_key(context);
+    let (material, len) = (JsData::new(material)?, length_option(options)?);
+    metered("deriveKey", material.len(), || {
+        material.update(&mut hasher);
+        fill_output(&hasher, len)
+    })
+}
+
+#[wasm_bindgen(js_name = createKeyed)]
+pub fn create_keyed(key: &[u8]) -> Result<NodeHash, JsError> {
+    Ok(NodeHash::from(blake3::Hasher::new_keyed(key_array(key)?)))
+}
+
+#[wasm_bindgen(js_name = createDeriveKey)]
+pub fn create_derive_key(context: &str) -> NodeHash {
+    NodeHash::from(blake3::Hasher::new_derive_key(context))
+}
+
+#[wasm_bindgen(js_class = Hash)]
+impl NodeHash {
+    pub fn update(&self, data: &JsValue) -> Result<NodeHash, JsError> {
+        let mut hasher = self.hasher.borrow_mut();
+        let hasher = hasher
+            .as_mut()
+            .ok_or_else(|| JsError::new("digest already called"))?;
+        let data = JsData::new(data)?;
+        metered("Hash.update", data.len(), || data.update(hasher));
+        Ok(NodeHash {
+            hasher: self.hasher.clone(),
+        })
+    }
+
+    // digest(encoding?, options?) or digest(options), where options is the
+    // blake3 npm package's { length }.
+    pub fn digest(&self, encoding: &JsValue, options: &JsValue) -> Result<JsValue, JsError> {
+        let (encoding, options) = match encoding.as_string() {
+            Some(encoding) => (Some(encoding), options),
+            None if encoding.is_object() => (None, encoding),
+            None => (None, options),
+        };
+        let len = length_option(options)?;
+        let mut hasher = self.hasher.borrow_mut();
+        let digest = fill_output(
+            hasher
+                .as_ref()
+                .ok_or_else(|| JsError::new("digest already called"))?,
+            len,
+        )?;
+        hasher.zeroize();
+        Ok(match encoding.as_deref() {
+            None => Uint8Array::from(&digest[..]).into(),
+            Some("hex") => hex_lower(&digest).into(),
+            Some("base64") => BASE64.encode(&digest).into(),
+            Some("base64url") => BASE64_URL.encode(&digest).into(),
+            Some(other) => {
+                return Err(JsError::new(&format!(
+                    "unsupported digest encoding: {other}"
+                )))
+            }
+        })
+    }
+}
+
+// The last handle on a shared state wipes it.
+impl Drop for NodeHash {
+    fn drop(&mut self) {
+        if let Some(hasher) = Rc::get_mut(&mut self.hasher) {
+            hasher.get_mut().zeroize();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct OutputReader {
+    inner: blake3::OutputReader,
+}
+
+impl Drop for OutputReader {
+    fn drop(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+#[wasm_bindgen]
+impl OutputReader {
+    pub fn read(&mut self, len: usize) -> Result<Vec<u8>, JsError> {
+        let mut out = output_buffer(len)?;
+        self.inner.fill(&mut out);
+        Ok(out)
+    }
+
+    pub fn position(&self) -> u64 {
+        self.inner.position()
+    }
+
+    pub fn seek(&mut self, position: U64Arg) -> Result<(), JsError> {
+        self.inner.set_position(u64_arg(position, "position")?);
+        Ok(())
+    }
+
+    // blake3 keeps the reader's root node private, so the state can't carry
+    // it: it is OUTPUT_READER_STATE_MAGIC, the position as a u64
+    // little-endian, and a fingerprint of the output derived one way from
+    // its first 32 bytes, so that it gives away no output.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut reader = self.inner.clone();
+        reader.set_position(0);
+        let mut root = Zeroizing::new([0; OUT_LEN]);
+        reader.fill(&mut *root);
+        reader.zeroize();
+        let mut state = OUTPUT_READER_STATE_MAGIC.to_vec();
+        state.extend_from_slice(&self.inner.position().to_le_bytes());
+        state.extend_from_slice(&blake3::derive_key(OUTPUT_READER_STATE_CONTEXT, &*root));
+        state
+    }
+
+    // Rebuilds a saved reader from a hasher that holds the same input,
+    // starting at the saved position without producing the output before it.
+    pub fn restore_state(hasher: &WasmHasher, state: &[u8]) -> Result<OutputReader, JsError> {
+        let (position, fingerprint) = state
+            .strip_prefix(OUTPUT_READER_STATE_MAGIC)
+            .filter(|rest| rest.len() == 8 + OUT_LEN)
+            .ok_or_else(|| JsError::new("not an output reader state"))?
+            .split_at(8);
+        let root = Zeroizing::new(*hasher.root_hasher()?.finalize().as_bytes());
+        let expected = blake3::derive_key(OUTPUT_READER_STATE_CONTEXT, &*root);
+        if blake3::Hash::from(expected) != parse_hash(fingerprint)? {
+            return Err(JsError::new("output reader state is for a different input"));
+        }
+        let mut reader = hasher.finalize_xof()?;
+        reader
+            .inner
+            .set_position(u64::from_le_bytes(position.try_into().unwrap()));
+        Ok(reader)
+    }
+}
+
+#[wasm_bindgen]
+pub struct KeyedStream {
+    reader: blake3::OutputReader,
+}
+
+impl Drop for KeyedStream {
+    fn drop(&mut self) {
+        self.reader.zeroize();
+    }
+}
+
+#[wasm_bindgen]
+impl KeyedStream {
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: &[u8]) -> Result<KeyedStream, JsError> {
+        Ok(KeyedStream {
+            reader: blake3::Hasher::new_keyed(key_array(seed)?).finalize_xof(),
+        })
+    }
+
+    pub fn next_bytes(&mut self, len: usize) -> Result<Vec<u8>, JsError> {
+        let mut out = output_buffer(len)?;
+        self.reader.fill(&mut out);
+        Ok(out)
+    }
+
+    pub fn fill(&mut self, out: &mut [u8]) {
+        self.reader.fill(out);
+    }
+
+    // The stream is fully determined by the seed and position, so saving
+    // both is enough to resume it later with seek.
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    pub fn seek(&mut self, position: U64Arg) -> Result<(), JsError> {
+        self.reader.set_position(u64_arg(position, "position")?);
+        Ok(())
+    }
+}
+
+// A 32-byte key kept in wasm, so keyed calls pass a handle rather than the
+// key bytes. destroy(), and free(), wipe it; a destroyed Key throws on use.
+#[wasm_bindgen]
+pub struct Key {
+    key: Option<Zeroizing<[u8; blake3::KEY_LEN]>>,
+}
+
+#[wasm_bindgen]
+impl Key {
+    // Accepts any ArrayBuffer or view, such as WebCrypto exportKey("raw")
+    // output, and copies it straight into the handle.
+    #[wasm_bindgen(constructor)]
+    pub fn new(key: &JsValue) -> Result<Key, JsError> {
+        let source = buffer_source(key)?;
+        if source.length() as usize != blake3::KEY_LEN {
+            return Err(JsError::new("key must be 32 bytes"));
+        }
+        let mut bytes = Zeroizing::new([0; blake3::KEY_LEN]);
+        source.copy_to(&mut bytes[..]);
+        Ok(Key { key: Some(bytes) })
+    }
+
+    fn bytes(&self) -> Result<&[u8; blake3::KEY_LEN], JsError> {
+        self.key
+            .as_deref()
+            .ok_or_else(|| JsError::new("key has been destroyed"))
+    }
+
+    pub fn keyed_hash(&self, input: &[u8]) -> Result<Vec<u8>, JsError> {
+        let key = self.bytes()?;
+        Ok(metered("Key.keyed_hash", input.len(), || {
+            blake3::keyed_hash(key, input).as_bytes().to_vec()
+        }))
+    }
+
+    pub fn verify(&self, input: &[u8], expected_tag: &[u8]) -> Result<bool, JsError> {
+        let expected = parse_hash(expected_tag)?;
+        let key = self.bytes()?;
+        Ok(metered("Key.verify", input.len(), || {
+            blake3::keyed_hash(key, input) == expected
+        }))
+    }
+
+    // A WasmHasher in keyed mode, for streaming input.
+    pub fn hasher(&self) -> Result<WasmHasher, JsError> {
+        Ok(WasmHasher::from(blake3::Hasher::new_keyed(self.bytes()?)))
+    }
+
+    // keyedHash and createKeyed with this key: data may be a string or
+    // any ArrayBuffer view, and options is { length }.
+    pub fn hash(&self, data: &JsValue, options: &JsValue) -> Result<Vec<u8>, JsError> {
+        let mut hasher = blake3::Hasher::new_keyed(self.bytes()?);
+        let (data, len) = (JsData::new(data)?, length_option(options)?);
+        metered("Key.hash", data.len(), || {
+            data.update(&mut hasher);
+            fill_output(&hasher, len)
+        })
+    }
+
+    #[wasm_bindgen(js_name = createHash)]
+    pub fn create_hash(&self) -> Result<NodeHash, JsError> {
+        Ok(NodeHash::from(blake3::Hasher::new_keyed(self.bytes()?)))
+    }
+
+    // derive_chunk_key with this key as the master key.
+    pub fn derive_chunk_key(
+        &self,
+        chunk_index: U64Arg,
+        output_len: usize,
+    ) -> Result<Vec<u8>, JsError> {
+        derive_chunk_key_with(
+            "Key.derive_chunk_key",
+            self.bytes()?,
+            chunk_index,
+            output_len,
+        )
+    }
+
+    pub fn destroy(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+// Keys for multi_digest held as Key handles, so the raw key bytes never
+// cross the boundary. add() copies the key in; clear(), and free(), wipe
+// the copies.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct KeySet {
+    keys: Zeroizing<Vec<[u8; blake3::KEY_LEN]>>,
+}
+
+#[wasm_bindgen]
+impl KeySet {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> KeySet {
+        KeySet::default()
+    }
+
+    pub fn add(&mut self, key: &Key) -> Result<(), JsError> {
+        self.keys.push(*key.bytes()?);
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.keys.len()
+    }
+
+    // Same output as multi_digest(input, keys) with the keys in the order
+    // they were added.
+    pub fn multi_digest(&self, input: &[u8]) -> Vec<u8> {
+        let mut hashers = Zeroizing::new(Vec::with_capacity(self.keys.len() + 1));
+        hashers.push(blake3::Hasher::new());
+        hashers.extend(self.keys.iter().map(blake3::Hasher::new_keyed));
+        multi_digest_with("KeySet.multi_digest", input, hashers)
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.zeroize();
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+// Merkle Mountain Range over 32-byte leaf digests. Nodes are prefixed as in
+// RFC 6962 so leaves, parents and the root can't stand in for each other:
+// leaf = H(0x00 || digest), parent = H(0x01 || left || right), and the root
+// bags the peaks as H(0x02 || leaf_count as u64 LE || peaks left to right).
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Mmr {
+    // levels[h][i] covers leaves i * 2^h up to (i + 1) * 2^h.
+    levels: Vec<Vec<blake3::Hash>>,
+}
+
+fn mmr_leaf(digest: &blake3::Hash) -> blake3::Hash {
+    blake3::Hasher::new()
+        .update(&[0])
+        .update(digest.as_bytes())
+        .finalize()
+}
+
+fn mmr_parent(left: &blake3::Hash, right: &blake3::Hash) -> blake3::Hash {
+    blake3::Hasher::new()
+        .update(&[1])
+        .update(left.as_bytes())
+        .update(right.as_bytes())
+        .finalize()
+}
+
+fn mmr_root(leaf_count: u64, peaks: &[blake3::Hash]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[2]).update(&leaf_count.to_le_bytes());
+    for peak in peaks {
+        hasher.update(peak.as_bytes());
+    }
+    hasher.finalize()
+}
+
+// Height and first leaf of each peak, left to right.
+fn mmr_peaks(leaf_count: u64) -> impl Iterator<Item = (u32, u64)> {
+    (0..u64::BITS)
+        .rev()
+        .filter(move |height| leaf_count >> height & 1 == 1)
+        .map(move |height| (height, leaf_count & !(u64::MAX >> (63 - height))))
+}
+
+fn mmr_leaf_digest(leaf_digest: &[u8]) -> Result<blake3::Hash, JsError> {
+    blake3::Hash::from_slice(leaf_digest).map_err(|_| JsError::new("leaf digest must be 32 bytes"))
+}
+
+// Index of the peak over leaf_index, and that peak's height; None unless
+// leaf_index < leaf_count.
+fn mmr_peak_of(leaf_count: u64, leaf_index: u64) -> Option<(usize, u32)> {
+    mmr_peaks(leaf_count)
+        .enumerate()
+        .find(|(_, (height, start))| leaf_index < start + (1 << height))
+        .map(|(peak, (height, _))| (peak, height))
+}
+
+fn mmr_checked_peak_of(leaf_count: u64, leaf_index: u64) -> Result<(usize, u32), JsError> {
+    mmr_peak_of(leaf_count, leaf_index)
+        .ok_or_else(|| JsError::new("leaf_index must be less than leaf_count"))
+}
+
+// A proof holds the siblings below the leaf's peak and every other peak.
+fn mmr_proof_len(leaf_count: u64, height: u32) -> usize {
+    (height as usize + leaf_count.count_ones() as usize - 1) * OUT_LEN
+}
+
+// Whether proof, as Mmr::prove_nodes builds it, takes leaf_digest at
+// leaf_index to root for leaf_count leaves. Out-of-range indices and proofs
+// of the wrong length don't verify.
+fn mmr_verify(
+    root: &blake3::Hash,
+    leaf_count: u64,
+    leaf_index: u64,
+    leaf_digest: &blake3::Hash,
+    proof: &[u8],
+) -> bool {
+    let Some((own_peak, height)) = mmr_peak_of(leaf_count, leaf_index) else {
+        return false;
+    };
+    if proof.len() != mmr_proof_len(leaf_count, height) {
+        return false;
+    }
+    let mut nodes = proof
+        .chunks_exact(OUT_LEN)
+        .map(|node| blake3::Hash::from_slice(node).unwrap());
+    let mut node = mmr_leaf(leaf_digest);
+    for (level, sibling) in (0..height).zip(&mut nodes) {
+        node = if leaf_index >> level & 1 == 0 {
+            mmr_parent(&node, &sibling)
+        } else {
+            mmr_parent(&sibling, &node)
+        };
+    }
+    let mut peaks: Vec<blake3::Hash> = nodes.collect();
+    peaks.insert(own_peak, node);
+    mmr_root(leaf_count, &peaks) == *root
+}
+
+impl Mmr {
+    fn push_leaf(&mut self, leaf_digest: &blake3::Hash) {
+        let mut node = mmr_leaf(leaf_digest);
+        for height in 0.. {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            let level = &mut self.levels[height];
+            level.push(node);
+            if level.len() % 2 == 1 {
+                break;
+            }
+            node = mmr_parent(&level[level.len() - 2], &level[level.len() - 1]);
+        }
+    }
+
+    fn peak_nodes(&self) -> Vec<blake3::Hash> {
+        mmr_peaks(self.leaf_count())
+            .map(|(height, start)| self.levels[height as usize][(start >> height) as usize])
+            .collect()
+    }
+
+    fn root_hash(&self) -> blake3::Hash {
+        mmr_root(self.leaf_count(), &self.peak_nodes())
+    }
+
+    // The leaf's sibling nodes from the bottom up, then every other peak
+    // left to right; None unless leaf_index < leaf_count.
+    fn prove_nodes(&self, leaf_index: u64) -> Option<Vec<u8>> {
+        let (own_peak, height) = mmr_peak_of(self.leaf_count(), leaf_index)?;
+        let mut proof = Vec::new();
+        for level in 0..height {
+            let sibling = (leaf_index >> level) ^ 1;
+            proof.extend_from_slice(self.levels[level as usize][sibling as usize].as_bytes());
+        }
+        for (peak, node) in self.peak_nodes().iter().enumerate() {
+            if peak != own_peak {
+                proof.extend_from_slice(node.as_bytes());
+            }
+        }
+        Some(proof)
+    }
+}
+
+#[wasm_bindgen]
+impl Mmr {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Mmr {
+        Mmr::default()
+    }
+
+    // Returns the new leaf's index.
+    pub fn append(&mut self, leaf_digest: &[u8]) -> Result<u64, JsError> {
+        let leaf_digest = mmr_leaf_digest(leaf_digest)?;
+        let index = self.leaf_count();
+        metered("Mmr.append", OUT_LEN, || self.push_leaf(&leaf_digest));
+        Ok(index)
+    }
+
+    pub fn leaf_count(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| leaves.len() as u64)
+    }
+
+    // The peaks' 32-byte nodes, concatenated left to right.
+    pub fn peaks(&self) -> Vec<u8> {
+        self.peak_nodes()
+            .iter()
+            .flat_map(|peak| *peak.as_bytes())
+            .collect()
+    }
+
+    pub fn root(&self) -> Vec<u8> {
+        self.root_hash().as_bytes().to_vec()
+    }
+
+    // A proof for the current leaf_count, for verify_mmr_proof.
+    pub fn prove(&self, leaf_index: U64Arg) -> Result<Vec<u8>, JsError> {
+        let leaf_index = u64_arg(leaf_index, "leaf_index")?;
+        mmr_checked_peak_of(self.leaf_count(), leaf_index)?;
+        Ok(self.prove_nodes(leaf_index).unwrap())
+    }
+}
+
+#[wasm_bindgen]
+pub fn verify_mmr_proof(
+    root: &[u8],
+    leaf_count: U64Arg,
+    leaf_index: U64Arg,
+    leaf_digest: &[u8],
+    proof: &[u8],
+) -> Result<bool, JsError> {
+    let root = parse_hash(root)?;
+    let leaf_count = u64_arg(leaf_count, "leaf_count")?;
+    let leaf_index = u64_arg(leaf_index, "leaf_index")?;
+    let leaf_digest = mmr_leaf_digest(leaf_digest)?;
+    let (_, height) = mmr_checked_peak_of(leaf_count, leaf_index)?;
+    let proof_len = mmr_proof_len(leaf_count, height);
+    if proof.len() != proof_len {
+        return Err(JsError::new(&format!(
+            "proof must be {proof_len} bytes for this leaf_count and leaf_index"
+        )));
+    }
+    Ok(metered("verify_mmr_proof", OUT_LEN + proof.len(), || {
+        mmr_verify(&root, leaf_count, leaf_index, &leaf_digest, proof)
+    }))
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn encode_bao(input: &[u8]) -> Result<Vec<u8>, JsError> {
+    check_alloc(bao::encode::encoded_size(input.len() as u64) as u64)?;
+    Ok(metered("encode_bao", input.len(), || {
+        bao::encode::encode(input).0
+    }))
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn encode_bao_outboard(input: &[u8]) -> Result<Vec<u8>, JsError> {
+    check_alloc(bao::encode::outboard_size(input.len() as u64) as u64)?;
+    Ok(metered("encode_bao_outboard", input.len(), || {
+        bao::encode::outboard(input).0
+    }))
+}
+
+// Builds an outboard encoding from input pushed in pieces, so the input
+// never has to be in memory at once. Only the tree is kept, about 1/16 of
+// the input, and finalize flips it into Bao's pre-order layout in place.
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub struct OutboardEncoder {
+    inner: Option<bao::encode::Encoder<Cursor<Vec<u8>>>>,
+    len: u64,
+    root: Option<blake3::Hash>,
+}
+
+#[cfg(feature = "bao")]
+impl Default for OutboardEncoder {
+    fn default() -> OutboardEncoder {
+        OutboardEncoder {
+            inner: Some(bao::encode::Encoder::new_outboard(Cursor::new(Vec::new()))),
+            len: 0,
+            root: None,
+        }
+    }
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+impl OutboardEncoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> OutboardEncoder {
+        OutboardEncoder::default()
+    }
+
+    // Like CountingAlloc::realloc, each update checks only what the tree
+    // buffer grows by, since the bytes it already holds are counted as live.
+    pub fn update(&mut self, input: &[u8]) -> Result<(), JsError> {
+        let encoder = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| JsError::new("finalize already called"))?;
+        let len = self.len + input.len() as u64;
+        let additional = bao::encode::outboard_size(len) - bao::encode::outboard_size(self.len);
+        check_alloc(additional as u64)?;
+        metered("OutboardEncoder.update", input.len(), || {
+            encoder.write_all(input)
+        })?;
+        self.len = len;
+        Ok(())
+    }
+
+    pub fn finalize(&mut self) -> Result<Vec<u8>, JsError> {
+        let mut encoder = self
+            .inner
+            .take()
+            .ok_or_else(|| JsError::new("finalize already called"))?;
+        self.root = Some(encoder.finalize()?);
+        Ok(encoder.into_inner().into_inner())
+    }
+
+    // The BLAKE3 hash of the input, once finalize has been called.
+    pub fn root(&self) -> Option<Vec<u8>> {
+        self.root.map(|root| root.as_bytes().to_vec())
+    }
+}
+
+// Sizes including the 8-byte header, as Numbers like plan_slice_ranges
+// offsets; sizes past 2^53 throw.
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn encoded_len(input_len: U64Arg) -> Result<f64, JsError> {
+    js_number(
+        bao::encode::encoded_size(u64_arg(input_len, "input_len")?),
+        "size",
+    )
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn outboard_len(input_len: U64Arg) -> Result<f64, JsError> {
+    js_number(
+        bao::encode::outboard_size(u64_arg(input_len, "input_len")?),
+        "size",
+    )
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn bao_extract_slice(encoded: &[u8], start: U64Arg, len: U64Arg) -> Result<Vec<u8>, JsError> {
+    let (start, len) = (u64_arg(start, "start")?, u64_arg(len, "len")?);
+    let mut slice = Vec::new();
+    SliceExtractor::new(Cursor::new(encoded), start, len).read_to_end(&mut slice)?;
+    Ok(slice)
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn bao_extract_slice_outboard(
+    content: &[u8],
+    outboard: &[u8],
+    start: U64Arg,
+    len: U64Arg,
+) -> Result<Vec<u8>, JsError> {
+    let (start, len) = (u64_arg(start, "start")?, u64_arg(len, "len")?);
+    let mut slice = Vec::new();
+    SliceExtractor::new_outboard(Cursor::new(content), Cursor::new(outboard), start, len)
+        .read_to_end(&mut slice)?;
+    Ok(slice)
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn bao_verify_slice(
+    slice: &[u8],
+    root: &[u8],
+    start: U64Arg,
+    len: U64Arg,
+) -> Result<Vec<u8>, JsError> {
+    let (start, len) = (u64_arg(start, "start")?, u64_arg(len, "len")?);
+    let root = parse_hash(root)?;
+    let mut content = Vec::new();
+    let result = metered("bao_verify_slice", slice.len(), || {
+        SliceDecoder::new(slice, &root, start, len).read_to_end(&mut content)
+    });
+    if let Err(e) = result {
+        let offset = start + content.len() as u64;
+        let chunk = offset / CHUNK_LEN as u64;
+        return Err(JsError::new(&format!(
+            "{e} at chunk {chunk} (content offset {offset})"
+        )));
+    }
+    Ok(content)
+}
+
+// Walks outboard's parents from root down to the chunk at offset and checks
+// each, then the chunk. outboard and chunk_data must already have the sizes
+// total_len gives them.
+#[cfg(feature = "bao")]
+fn outboard_chunk_matches(
+    chunk_data: &[u8],
+    offset: u64,
+    outboard: &[u8],
+    root: blake3::Hash,
+    total_len: u64,
+) -> bool {
+    let mut pos = HEADER_LEN;
+    let mut node = Subtree {
+        cv: root,
+        offset: 0,
+        len: total_len,
+        root: true,
+    };
+    while node.len > CHUNK_LEN as u64 {
+        let parent = outboard[pos..].first_chunk::<PARENT_LEN>().unwrap();
+        let (cv, left, right) = parent_node_cv(parent, node.root);
+        if cv != node.cv {
+            return false;
+        }
+        let left_len = hazmat::left_subtree_len(node.len);
+        pos += PARENT_LEN;
+        node = if offset < node.offset + left_len {
+            Subtree {
+                cv: left.into(),
+                offset: node.offset,
+                len: left_len,
+                root: false,
+            }
+        } else {
+            pos += encoded_subtree_len(left_len, true) as usize;
+            Subtree {
+                cv: right.into(),
+                offset: node.offset + left_len,
+                len: node.len - left_len,
+                root: false,
+            }
+        };
+    }
+    chunk_cv(chunk_data, node.offset, node.root) == node.cv
+}
+
+// Checks one chunk against an outboard encoding by walking only the parents
+// from the root down to it. Arguments that don't fit total_len throw; a
+// chunk or parent that doesn't match returns false.
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn verify_chunk_with_outboard(
+    chunk_data: &[u8],
+    chunk_index: U64Arg,
+    outboard: &[u8],
+    root: &[u8],
+    total_len: U64Arg,
+) -> Result<bool, JsError> {
+    let chunk_index = u64_arg(chunk_index, "chunk_index")?;
+    let total_len = u64_arg(total_len, "total_len")?;
+    let root = parse_hash(root)?;
+    let offset = chunk_index
+        .checked_mul(CHUNK_LEN as u64)
+        .filter(|&offset| offset < total_len.max(1))
+        .ok_or_else(|| JsError::new("chunk_index is past the end of the content"))?;
+    let chunk_len = (total_len - offset).min(CHUNK_LEN as u64);
+    if chunk_data.len() as u64 != chunk_len {
+        return Err(JsError::new(&format!(
+            "chunk {chunk_index} must be {chunk_len} bytes"
+        )));
+    }
+    let outboard_len = HEADER_LEN as u64 + encoded_subtree_len(total_len, true);
+    if outboard.len() as u64 != outboard_len {
+        return Err(JsError::new(&format!(
+            "outboard must be {outboard_len} bytes for total_len"
+        )));
+    }
+    if outboard[..HEADER_LEN] != total_len.to_le_bytes() {
+        return Ok(false);
+    }
+    let matches = metered("verify_chunk_with_outboard", chunk_data.len(), || {
+        outboard_chunk_matches(chunk_data, offset, outboard, root, total_len)
+    });
+    Ok(matches)
+}
+
+// The offset of the first parent in outboard that doesn't match the CV its
+// own parent (or root) gives it, or None if they all do. outboard must
+// already be the size total_len gives it.
+#[cfg(feature = "bao")]
+fn outboard_mismatch(outboard: &[u8], root: blake3::Hash, total_len: u64) -> Option<usize> {
+    // Parents are stored in pre-order, so they are read front to back while
+    // the stack holds the subtrees still to visit, left child on top.
+    let mut pos = HEADER_LEN;
+    let mut stack = vec![Subtree {
+        cv: root,
+        offset: 0,
+        len: total_len,
+        root: true,
+    }];
+    while let Some(node) = stack.pop() {
+        if node.len <= CHUNK_LEN as u64 {
+            continue;
+        }
+        let parent = outboard[pos..].first_chunk::<PARENT_LEN>().unwrap();
+        let (cv, left, right) = parent_node_cv(parent, node.root);
+        if cv != node.cv {
+            return Some(pos);
+        }
+        pos += PARENT_LEN;
+        let left_len = hazmat::left_subtree_len(node.len);
+        stack.push(Subtree {
+            cv: right.into(),
+            offset: node.offset + left_len,
+            len: node.len - left_len,
+            root: false,
+        });
+        stack.push(Subtree {
+            cv: left.into(),
+            offset: node.offset,
+            len: left_len,
+            root: false,
+        });
+    }
+    None
+}
+
+// Checks that every parent node in an outboard encoding matches the CV its
+// own parent (or the root) gives it, without the content. Chunks are not
+// covered; verify_chunk_with_outboard checks those one at a time.
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn validate_outboard(outboard: &[u8], root: &[u8], total_len: U64Arg) -> Result<(), JsError> {
+    let total_len = u64_arg(total_len, "total_len")?;
+    let root = parse_hash(root)?;
+    let outboard_len = HEADER_LEN as u64 + encoded_subtree_len(total_len, true);
+    if outboard.len() as u64 != outboard_len {
+        return Err(JsError::new(&format!(
+            "outboard must be {outboard_len} bytes for total_len"
+        )));
+    }
+    if outboard[..HEADER_LEN] != total_len.to_le_bytes() {
+        return Err(JsError::new("outboard header does not match total_len"));
+    }
+    let mismatch = metered("validate_outboard", outboard.len(), || {
+        outboard_mismatch(outboard, root, total_len)
+    });
+    match mismatch {
+        Some(pos) => Err(JsError::new(&format!(
+            "hash mismatch at outboard offset {pos}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+// Number of bytes a subtree of len content bytes occupies in a combined
+// encoding, or in an outboard.
+#[cfg(feature = "bao")]
+fn encoded_subtree_len(len: u64, outboard: bool) -> u64 {
+    let chunks = len.div_ceil(CHUNK_LEN as u64).max(1);
+    let parents = (chunks - 1) * PARENT_LEN as u64;
+    if outboard {
+        parents
+    } else {
+        parents + len
+    }
+}
+
+// The content range a Bao slice covers, mirroring bao's SliceExtractor: at
+// least one byte, and a start past the end selects the final chunk.
+#[cfg(feature = "bao")]
+fn slice_selection(content_len: u64, start: u64, len: u64) -> (u64, u64) {
+    if start < content_len {
+        (start, content_len.min(start.saturating_add(len.max(1))))
+    } else {
+        let last = content_len.saturating_sub(1);
+        (last, last + 1)
+    }
+}
+
+// One entry of a slice plan: bytes [start, end) of either the encoding
+// ("encoding", or "outboard" for outboard plans) or the content ("content").
+#[cfg(feature = "bao")]
+#[derive(Serialize)]
+struct SliceRange {
+    source: &'static str,
+    start: u64,
+    end: u64,
+}
+
+// Collects, in slice order, the byte ranges whose concatenation is the Bao
+// slice for a content range.
+#[cfg(feature = "bao")]
+struct SlicePlan {
+    outboard: bool,
+    start: u64,
+    end: u64,
+    ranges: Vec<SliceRange>,
+}
+
+#[cfg(feature = "bao")]
+impl SlicePlan {
+    // The plan for the slice of [start, start + len) of a content_len-byte
+    // input.
+    fn ranges(content_len: u64, start: u64, len: u64, outboard: bool) -> Vec<SliceRange> {
+        let (start, end) = slice_selection(content_len, start, len);
+        let mut plan = SlicePlan {
+            outboard,
+            start,
+            end,
+            ranges: Vec::new(),
+        };
+        plan.push(plan.tree_source(), 0, HEADER_LEN as u64);
+        plan.visit(0, content_len, HEADER_LEN as u64);
+        plan.ranges
+    }
+
+    fn push(&mut self, source: &'static str, start: u64, end: u64) {
+        if let Some(prev) = self.ranges.last_mut() {
+            if prev.source == source && prev.end == start {
+                prev.end = end;
+                return;
+            }
+        }
+        self.ranges.push(SliceRange { source, start, end });
+    }
+
+    fn tree_source(&self) -> &'static str {
+        if self.outboard {
+            "outboard"
+        } else {
+            "encoding"
+        }
+    }
+
+    fn overlaps(&self, offset: u64, len: u64) -> bool {
+        offset < self.end && self.start < offset + len
+    }
+
+    fn visit(&mut self, offset: u64, len: u64, pos: u64) {
+        if len > CHUNK_LEN as u64 {
+            self.push(self.tree_source(), pos, pos + PARENT_LEN as u64);
+            let left_len = hazmat::left_subtree_len(len);
+            let right_pos = pos + PARENT_LEN as u64 + encoded_subtree_len(left_len, self.outboard);
+            if self.overlaps(offset, left_len) {
+                self.visit(offset, left_len, pos + PARENT_LEN as u64);
+            }
+            if self.overlaps(offset + left_len, len - left_len) {
+                self.visit(offset + left_len, len - left_len, right_pos);
+            }
+        } else if self.outboard {
+            self.push("content", offset, offset + len);
+        } else {
+            self.push("encoding", pos, pos + len);
+        }
+    }
+}
+
+// Offsets are Numbers; plans with offsets past 2^53 throw.
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub fn plan_slice_ranges(
+    content_len: U64Arg,
+    start: U64Arg,
+    len: U64Arg,
+    outboard: bool,
+) -> Result<JsValue, JsValue> {
+    let content_len = u64_arg(content_len, "content_len")?;
+    let (start, len) = (u64_arg(start, "start")?, u64_arg(len, "len")?);
+    if bao::encode::encoded_size(content_len) > u64::MAX as u128 {
+        return Err(JsError::new("encoding would exceed 2^64 bytes").into());
+    }
+    to_js(&SlicePlan::ranges(content_len, start, len, outboard))
+}
+
+// The CV of a parent node, and its left and right child CVs.
+#[cfg(feature = "bao")]
+fn parent_node_cv(
+    parent: &[u8; PARENT_LEN],
+    root: bool,
+) -> (blake3::Hash, ChainingValue, ChainingValue) {
+    let (left, right) = parent.split_at(OUT_LEN);
+    let left: ChainingValue = left.try_into().unwrap();
+    let right: ChainingValue = right.try_into().unwrap();
+    let cv = if root {
+        hazmat::merge_subtrees_root(&left, &right, Mode::Hash)
+    } else {
+        hazmat::merge_subtrees_non_root(&left, &right, Mode::Hash).into()
+    };
+    (cv, left, right)
+}
+
+#[cfg(any(feature = "bao", feature = "hazmat"))]
+fn chunk_cv(chunk: &[u8], offset: u64, root: bool) -> blake3::Hash {
+    if root {
+        blake3::hash(chunk)
+    } else {
+        blake3::Hasher::new()
+            .set_input_offset(offset)
+            .update(chunk)
+            .finalize_non_root()
+            .into()
+    }
+}
+
+#[cfg(feature = "bao")]
+#[derive(Clone, Copy)]
+struct Subtree {
+    cv: blake3::Hash,
+    offset: u64,
+    len: u64,
+    root: bool,
+}
+
+// Incrementally verifies a Bao slice against a trusted root. A combined
+// encoding is the slice of its whole content. Content in [start, start +
+// len) is released as soon as the chunk holding it and all of that chunk's
+// parents check out.
+#[cfg(feature = "bao")]
+struct SliceVerifier {
+    root: blake3::Hash,
+    start: u64,
+    len: u64,
+    buf: Vec<u8>,
+    // Set from the header: the content range the slice covers, and the end
+    // of the range to output.
+    covered: Option<(u64, u64)>,
+    output_end: u64,
+    stack: Vec<Subtree>,
+}
+
+#[cfg(feature = "bao")]
+impl SliceVerifier {
+    fn new(root: &[u8], start: u64, len: u64) -> Result<SliceVerifier, JsError> {
+        Ok(SliceVerifier {
+            root: parse_hash(root)?,
+            start,
+            len,
+            buf: Vec::new(),
+            covered: None,
+            output_end: 0,
+            stack: Vec::new(),
+        })
+    }
+
+    fn overlaps(&self, offset: u64, len: u64) -> bool {
+        let (start, end) = self.covered.unwrap_or_default();
+        offset < end && start < offset + len
+    }
+
+    // Verifies the node on top of the stack if buffered input from pos holds
+    // all of it. A parent is replaced by those of its children the slice
+    // covers, a chunk is appended to out. Returns the number of bytes
+    // consumed, or None if more input is needed.
+    fn verify_next(&mut self, pos: usize, out: &mut Vec<u8>) -> Result<Option<usize>, JsError> {
+        let input = &self.buf[pos..];
+        let Some(&node) = self.stack.last() else {
+            return Ok(None);
+        };
+        let mismatch = || {
+            JsError::new(&format!(
+                "hash mismatch at chunk {}",
+                node.offset / CHUNK_LEN as u64
+            ))
+        };
+
+        if node.len > CHUNK_LEN as u64 {
+            let Some(parent) = input.first_chunk::<PARENT_LEN>() else {
+                return Ok(None);
+            };
+            let (cv, left, right) = parent_node_cv(parent, node.root);
+            if cv != node.cv {
+                return Err(mismatch());
+            }
+            let left_len = hazmat::left_subtree_len(node.len);
+            self.stack.pop();
+            if self.overlaps(node.offset + left_len, node.len - left_len) {
+                self.stack.push(Subtree {
+                    cv: right.into(),
+                    offset: node.offset + left_len,
+                    len: node.len - left_len,
+                    root: false,
+                });
+            }
+            if self.overlaps(node.offset, left_len) {
+                self.stack.push(Subtree {
+                    cv: left.into(),
+                    offset: node.offset,
+                    len: left_len,
+                    root: false,
+                });
+            }
+            Ok(Some(PARENT_LEN))
+        } else {
+            let Some(chunk) = input.get(..node.len as usize) else {
+                return Ok(None);
+            };
+            if chunk_cv(chunk, node.offset, node.root) != node.cv {
+                return Err(mismatch());
+            }
+            let from = self.start.max(node.offset);
+            let to = self.output_end.min(node.offset + node.len);
+            if from < to {
+                out.extend_from_slice(
+                    &chunk[(from - node.offset) as usize..(to - node.offset) as usize],
+                );
+            }
+            self.stack.pop();
+            Ok(Some(node.len as usize))
+        }
+    }
+
+    // Bytes verified before a failure in the same push are still returned;
+    // the failure is then reported by the next push or finish.
+    fn push(&mut self, input: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.buf.extend_from_slice(input);
+        if self.covered.is_none() {
+            let Some(header) = self.buf.first_chunk::<HEADER_LEN>() else {
+                return Ok(Vec::new());
+            };
+            let content_len = u64::from_le_bytes(*header);
+            self.covered = Some(slice_selection(content_len, self.start, self.len));
+            self.output_end = content_len.min(self.start.saturating_add(self.len));
+            self.stack.push(Subtree {
+                cv: self.root,
+                offset: 0,
+                len: content_len,
+                root: true,
+            });
+            self.buf.drain(..HEADER_LEN);
+        }
+
+        let mut out = Vec::new();
+        let mut pos = 0;
+        let result = loop {
+            match self.verify_next(pos, &mut out) {
+                Ok(Some(n)) => pos += n,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        self.buf.drain(..pos);
+
+        match result {
+            Err(e) if out.is_empty() => Err(e),
+            _ if out.is_empty() && self.stack.is_empty() && !self.buf.is_empty() => {
+                Err(JsError::new("trailing bytes after end of encoding"))
+            }
+            _ => Ok(out),
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), JsError> {
+        // Surfaces a failure or trailing bytes left pending by push.
+        self.push(&[])?;
+        if self.covered.is_none() || !self.stack.is_empty() {
+            return Err(JsError::new("encoding is truncated"));
+        }
+        Ok(())
+    }
+
+    // VERIFIER_STATE_MAGIC, the root, start, len, the covered range and
+    // output_end, a covered flag byte, the stack size and each Subtree as cv,
+    // offset, len and root byte, then the buffered input. Integers are u64 LE.
+    // Pending CVs are trusted on restore, so the state must be stored as
+    // safely as the root itself.
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = VERIFIER_STATE_MAGIC.to_vec();
+        state.extend_from_slice(self.root.as_bytes());
+        let (covered_start, covered_end) = self.covered.unwrap_or_default();
+        for n in [
+            self.start,
+            self.len,
+            covered_start,
+            covered_end,
+            self.output_end,
+        ] {
+            state.extend_from_slice(&n.to_le_bytes());
+        }
+        state.push(self.covered.is_some() as u8);
+        state.extend_from_slice(&(self.stack.len() as u64).to_le_bytes());
+        for node in &self.stack {
+            state.extend_from_slice(node.cv.as_bytes());
+            state.extend_from_slice(&node.offset.to_le_bytes());
+            state.extend_from_slice(&node.len.to_le_bytes());
+            state.push(node.root as u8);
+        }
+        state.extend_from_slice(&self.buf);
+        state
+    }
+
+    fn restore_state(root: &[u8], state: &[u8]) -> Result<SliceVerifier, JsError> {
+        fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8], JsError> {
+            let (head, tail) = rest
+                .split_at_checked(n)
+                .ok_or_else(|| JsError::new("verifier state is truncated"))?;
+            *rest = tail;
+            Ok(head)
+        }
+        fn take_u64(rest: &mut &[u8]) -> Result<u64, JsError> {
+            Ok(u64::from_le_bytes(take(rest, 8)?.try_into().unwrap()))
+        }
+
+        let mut rest = state
+            .strip_prefix(VERIFIER_STATE_MAGIC)
+            .ok_or_else(|| JsError::new("not a verifier state"))?;
+        if take(&mut rest, OUT_LEN)? != root {
+            return Err(JsError::new("verifier state is for a different root"));
+        }
+        let mut verifier = SliceVerifier::new(root, take_u64(&mut rest)?, take_u64(&mut rest)?)?;
+        let covered = (take_u64(&mut rest)?, take_u64(&mut rest)?);
+        verifier.output_end = take_u64(&mut rest)?;
+        if take(&mut rest, 1)?[0] != 0 {
+            verifier.covered = Some(covered);
+        }
+        for _ in 0..take_u64(&mut rest)? {
+            verifier.stack.push(Subtree {
+                cv: parse_hash(take(&mut rest, OUT_LEN)?)?,
+                offset: take_u64(&mut rest)?,
+                len: take_u64(&mut rest)?,
+                root: take(&mut rest, 1)?[0] != 0,
+            });
+        }
+        verifier.buf = rest.to_vec();
+        Ok(verifier)
+    }
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub struct VerifiedDecoder {
+    inner: SliceVerifier,
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+impl VerifiedDecoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(root: &[u8]) -> Result<VerifiedDecoder, JsError> {
+        Ok(VerifiedDecoder {
+            inner: SliceVerifier::new(root, 0, u64::MAX)?,
+        })
+    }
+
+    pub fn push(&mut self, encoded: &[u8]) -> Result<Vec<u8>, JsError> {
+        metered("VerifiedDecoder.push", encoded.len(), || {
+            self.inner.push(encoded)
+        })
+    }
+
+    pub fn finish(&mut self) -> Result<(), JsError> {
+        self.inner.finish()
+    }
+
+    // Progress to persist across restarts; resume by pushing input from where
+    // the last push before save_state ended.
+    pub fn save_state(&self) -> Vec<u8> {
+        self.inner.save_state()
+    }
+
+    pub fn restore_state(root: &[u8], state: &[u8]) -> Result<VerifiedDecoder, JsError> {
+        Ok(VerifiedDecoder {
+            inner: SliceVerifier::restore_state(root, state)?,
+        })
+    }
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+pub struct RangeVerifier {
+    inner: SliceVerifier,
+}
+
+#[cfg(feature = "bao")]
+#[wasm_bindgen]
+impl RangeVerifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(root: &[u8], start: U64Arg, len: U64Arg) -> Result<RangeVerifier, JsError> {
+        let (start, len) = (u64_arg(start, "start")?, u64_arg(len, "len")?);
+        Ok(RangeVerifier {
+            inner: SliceVerifier::new(root, start, len)?,
+        })
+    }
+
+    pub fn push(&mut self, slice: &[u8]) -> Result<Vec<u8>, JsError> {
+        metered("RangeVerifier.push", slice.len(), || self.inner.push(slice))
+    }
+
+    pub fn finish(&mut self) -> Result<(), JsError> {
+        self.inner.finish()
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.inner.save_state()
+    }
+
+    pub fn restore_state(root: &[u8], state: &[u8]) -> Result<RangeVerifier, JsError> {
+        Ok(RangeVerifier {
+            inner: SliceVerifier::restore_state(root, state)?,
+        })
+    }
+}
+
+fn now_ms() -> Result<f64, JsValue> {
+    let performance = Reflect::get(&js_sys::global(), &"performance".into())?;
+    let now: Function = Reflect::get(&performance, &"now".into())?.dyn_into()?;
+    Ok(now.call0(&performance)?.as_f64().unwrap_or_default())
+}
+
+// Hashes num_bytes of synthetic data iterations times and returns the
+// throughput in MB/s (10^6 bytes per second).
+#[wasm_bindgen]
+pub fn benchmark(num_bytes: usize, iterations: u32) -> Result<f64, JsValue> {
+    if num_bytes == 0 || iterations == 0 {
+        return Err(JsError::new("num_bytes and iterations must be non-zero").into());
+    }
+    let mut input = output_buffer(num_bytes)?;
+    for (i, byte) in input.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let start = now_ms()?;
+    for _ in 0..iterations {
+        std::hint::black_box(blake3::hash(std::hint::black_box(&input)));
+    }
+    let seconds = (now_ms()? - start) / 1000.0;
+    if seconds <= 0.0 {
+        return Err(JsError::new("run too short to time; raise num_bytes or iterations").into());
+    }
+    Ok(num_bytes as f64 * iterations as f64 / seconds / 1e6)
+}
+
+// The cases of BLAKE3's test_vectors/test_vectors.json: its 35 input lengths,
+// key and context, input byte i = i % 251, and 131 bytes of extended output
+// in each mode. The hex was generated with the reference C implementation in
+// the blake3 1.8.3 crate and matches the Rust implementation; it is not a
+// copy of the JSON file. Each case is checked one-shot, in 1000-byte updates,
+// and above one chunk through the hazmat subtree and parent path.
+const SELF_TEST_KEY: &[u8; blake3::KEY_LEN] = b"whats the Elvish word for friend";
+const SELF_TEST_CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+const SELF_TEST_XOF_LEN: usize = 131;
+// A 131-byte output as hex, split to fit the line width.
+type SelfTestHex = [&'static str; 4];
+const SELF_TEST_VECTORS: [(usize, SelfTestHex, SelfTestHex, SelfTestHex); 35] = [
+    (
+        0,
+        [
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262e0",
+            "0f03e7b69af26b7faaf09fcd333050338ddfe085b8cc869ca98b206c08243a26f5",
+            "487789e8f660afe6c99ef9e0c52b92e7393024a80459cf91f476f9ffdbda7001c2",
+            "2e159b402631f277ca96f2defdf1078282314e763699a31c5363165421cce14d",
+        ],
+        [
+            "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26b1",
+            "8171a2f22a4b94822c701f107153dba24918c4bae4d2945c20ece13387627d3b73",
+            "cbf97b797d5e59948c7ef788f54372df45e45e4293c7dc18c1d41144a9758be589",
+            "60856be1eabbe22c2653190de560ca3b2ac4aa692a9210694254c371e851bc8f",
+        ],
+        [
+            "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d90",
+            "5630c8be290dfcf3e6842f13bddd573c098c3f17361f1f206b8cad9d088aa4a3f7",
+            "46752c6b0ce6a83b0da81d59649257cdf8eb3e9f7d4998e41021fac119deefb896",
+            "224ac99f860011f73609e6e0e4540f93b273e56547dfd3aa1a035ba6689d89a0",
+        ],
+    ),
+    (
+        1,
+        [
+            "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213c3",
+            "a6cb8bf623e20cdb535f8d1a5ffb86342d9c0b64aca3bce1d31f60adfa137b358a",
+            "d4d79f97b47c3d5e79f179df87a3b9776ef8325f8329886ba42f07fb138bb502f4",
+            "081cbcec3195c5871e6c23e2cc97d3c69a613eba131e5f1351f3f1da786545e5",
+        ],
+        [
+            "6d7878dfff2f485635d39013278ae14f1454b8c0a3a2d34bc1ab38228a80c95b65",
+            "68c0490609413006fbd428eb3fd14e7756d90f73a4725fad147f7bf70fd61c4e0c",
+            "f7074885e92b0e3f125978b4154986d4fb202a3f331a3fb6cf349a3a70e49990f9",
+            "8fe4289761c8602c4e6ab1138d31d3b62218078b2f3ba9a88e1d08d0dd4cea11",
+        ],
+        [
+            "b3e2e340a117a499c6cf2398a19ee0d29cca2bb7404c73063382693bf66cb06c58",
+            "27b91bf889b6b97c5477f535361caefca0b5d8c4746441c5761711193315895067",
+            "0f9aa8a05d791daae10ac683cbef8faf897c84e6114a59d2173c3f417023a35d69",
+            "83f2c7dfa57e7fc559ad751dbfb9ffab39c2ef8c4aafebc9ae973a64f0c76551",
+        ],
+    ),
+    (
+        2,
+        [
+            "7b7015bb92cf0b318037702a6cdd81dee41224f734684c2c122cd6359cb1ee63d8",
+            "386b22e2ddc05836b7c1bb693d92af006deb5ffbc4c70fb44d0195d0c6f252faac",
+            "61659ef86523aa16517f87cb5f1340e723756ab65efb2f91964e14391de2a43226",
+            "3a6faf1d146937b35a33621c12d00be8223a7f1919cec0acd12097ff3ab00ab1",
+        ],
+        [
+            "5392ddae0e0a69d5f40160462cbd9bd889375082ff224ac9c758802b7a6fd20a9f",
+            "fbf7efd13e989a6c246f96d3a96b9d279f2c4e63fb0bdff633957acf50ee1a5f65",
+            "8be144bab0f6f16500dee4aa5967fc2c586d85a04caddec90fffb7633f46a60786",
+            "024353b9e5cebe277fcd9514217fee2267dcda8f7b31697b7c54fab6a939bf8f",
+        ],
+        [
+            "1f166565a7df0098ee65922d7fea425fb18b9943f19d6161e2d17939356168e6da",
+            "a59cae19892b2d54f6fc9f475d26031fd1c22ae0a3e8ef7bdb23f452a15e002762",
+            "9d2e867b1bb1e6ab21c71297377750826c404dfccc2406bd57a83775f89e0b075e",
+            "59a7732326715ef912078e213944f490ad68037557518b79c0086de6d6f6cdd2",
+        ],
+    ),
+    (
+        3,
+        [
+            "e1be4d7a8ab5560aa4199eea339849ba8e293d55ca0a81006726d184519e647f5b",
+            "49b82f805a538c68915c1ae8035c900fd1d4b13902920fd05e1450822f36de9454",
+            "b7e9996de4900c8e723512883f93f4345f8a58bfe64ee38d3ad71ab027765d25cd",
+            "d0e448328a8e7a683b9a6af8b0af94fa09010d9186890b096a08471e4230a134",
+        ],
+        [
+            "39e67b76b5a007d4921969779fe666da67b5213b096084ab674742f0d5ec62b9b9",
+            "142d0fab08e1b161efdbb28d18afc64d8f72160c958e53a950cdecf91c1a1bbab1",
+            "a9c0f01def762a77e2e8545d4dec241e98a89b6db2e9a5b070fc110caae2622690",
+            "bd7b76c02ab60750a3ea75426a6bb8803c370ffe465f07fb57def95df772c39f",
+        ],
+        [
+            "440aba35cb006b61fc17c0529255de438efc06a8c9ebf3f2ddac3b5a86705797f2",
+            "7e2e914574f4d87ec04c379e12789eccbfbc15892626042707802dbe4e97c3ff59",
+            "dca80c1e54246b6d055154f7348a39b7d098b2b4824ebe90e104e763b2a4475121",
+            "32cede16243484a55a4e40a85790038bb0dcf762e8c053cabae41bbe22a5bff7",
+        ],
+    ),
+    (
+        4,
+        [
+            "f30f5ab28fe047904037f77b6da4fea1e27241c5d132638d8bedce9d40494f328f",
+            "603ba4564453e06cdcee6cbe728a4519bbe6f0d41e8a14b5b225174a566dbfa61b",
+            "56afb1e452dc08c804f8c3143c9e2cc4a31bb738bf8c1917b55830c6e657972117",
+            "01dc0b98daa1faeaa6ee9e56ab606ce03a1a881e8f14e87a4acf4646272cfd12",
+        ],
+        [
+            "7671dde590c95d5ac9616651ff5aa0a27bee5913a348e053b8aa9108917fe07011",
+            "6c0acff3f0d1fa97ab38d813fd46506089118147d83393019b068a55d646251ecf",
+            "81105f798d76a10ae413f3d925787d6216a7eb444e510fd56916f1d753a5544ecf",
+            "0072134a146b2615b42f50c179f56b8fae0788008e3e27c67482349e249cb86a",
+        ],
+        [
+            "f46085c8190d69022369ce1a18880e9b369c135eb93f3c63550d3e7630e91060fb",
+            "d7d8f4258bec9da4e05044f88b91944f7cab317a2f0c18279629a3867fad0662c9",
+            "ad4d42c6f27e5b124da17c8c4f3a94a025ba5d1b623686c6099d202a7317a82e3d",
+            "95dae46a87de0555d727a5df55de44dab799a20dffe239594d6e99ed17950910",
+        ],
+    ),
+    (
+        5,
+        [
+            "b40b44dfd97e7a84a996a91af8b85188c66c126940ba7aad2e7ae6b385402aa2eb",
+            "cfdac6c5d32c31209e1f81a454751280db64942ce395104e1e4eaca62607de1c2c",
+            "a748251754ea5bbe8c20150e7f47efd57012c63b3c6a6632dc1c7cd15f3e1c9999",
+            "04037d60fac2eb9397f2adbe458d7f264e64f1e73aa927b30988e2aed2f03620",
+        ],
+        [
+            "73ac69eecf286894d8102018a6fc729f4b1f4247d3703f69bdc6a5fe3e0c84616a",
+            "b199d1f2f3e53bffb17f0a2209fe8b4f7d4c7bae59c2bc7d01f1ff94c67588cc6b",
+            "38fa6024886f2c078bfe09b5d9e6584cd6c521c3bb52f4de7687b37117a2dbbec0",
+            "d59e92fa9a8cc3240d4432f91757aabcae03e87431dac003e7d73574bfdd8218",
+        ],
+        [
+            "1f24eda69dbcb752847ec3ebb5dd42836d86e58500c7c98d906ecd82ed9ae47f6f",
+            "48a3f67e4e43329c9a89b1ca526b9b35cbf7d25c1e353baffb590fd79be58ddb6c",
+            "711f1a6b60e98620b851c688670412fcb0435657ba6b638d21f0f2a04f2f6b0bd8",
+            "834837b10e438d5f4c7c2c71299cf7586ea9144ed09253d51f8f54dd6bff719d",
+        ],
+    ),
+    (
+        6,
+        [
+            "06c4e8ffb6872fad96f9aaca5eee1553eb62aed0ad7198cef42e87f6a616c84461",
+            "1a30c4e4f37fe2fe23c0883cde5cf7059d88b657c7ed2087e3d210925ede716435",
+            "d6d5d82597a1e52b9553919e804f5656278bd739880692c94bff2824d8e0b48cac",
+            "1d24682699e4883389dc4f2faa2eb3b4db6e39debd5061ff3609916f3e07529a",
+        ],
+        [
+            "82d3199d0013035682cc7f2a399d4c212544376a839aa863a0f4c91220ca7a6dc2",
+            "ffb3aa05f2631f0fa9ac19b6e97eb7e6669e5ec254799350c8b8d189e880780084",
+            "2a5383c4d907c932f34490aaf00064de8cdb157357bde37c1504d2960034930887",
+            "603abc5ccb9f5247f79224baff6120a3c622a46d7b1bcaee02c5025460941256",
+        ],
+        [
+            "be96b30b37919fe4379dfbe752ae77b4f7e2ab92f7ff27435f76f2f065f6a5f435",
+            "ae01a1d14bd5a6b3b69d8cbd35f0b01ef2173ff6f9b640ca0bd4748efa398bf9a9",
+            "c0acd6a66d9332fdc9b47ffe28ba7ab6090c26747b85f4fab22f936b71eb3f6461",
+            "3d8bd9dfabe9bb68da19de78321b481e5297df9e40ec8a3d662f3e1479c65de0",
+        ],
+    ),
+    (
+        7,
+        [
+            "3f8770f387faad08faa9d8414e9f449ac68e6ff0417f673f602a646a891419fe66",
+            "036ef6e6d1a8f54baa9fed1fc11c77cfb9cff65bae915045027046ebe0c01bf5a9",
+            "41f3bb0f73791d3fc0b84370f9f30af0cd5b0fc334dd61f70feb60dad785f070fe",
+            "f1f343ed933b49a5ca0d16a503f599a365a4296739248b28d1a20b0e2cc8975c",
+        ],
+        [
+            "af0a7ec382aedc0cfd626e49e7628bc7a353a4cb108855541a5651bf64fbb28a7c",
+            "5035ba0f48a9c73dabb2be0533d02e8fd5d0d5639a18b2803ba6bf527e1d145d5f",
+            "d6406c437b79bcaad6c7bdf1cf4bd56a893c3eb9510335a7a798548c6753f74617",
+            "bede88bef924ba4b334f8852476d90b26c5dc4c3668a2519266a562c6c8034a6",
+        ],
+        [
+            "dc3b6485f9d94935329442916b0d059685ba815a1fa2a14107217453a7fc9f0e66",
+            "266db2ea7c96843f9d8208e600a73f7f45b2f55b9e6d6a7ccf05daae63a3fdd10b",
+            "25ac0bd2e224ce8291f88c05976d575df998477db86fb2cfbbf91725d62cb57acf",
+            "eb3c2d973b89b503c2b60dde85a7802b69dc1ac2007d5623cbea8cbfb6b181f5",
+        ],
+    ),
+    (
+        8,
+        [
+            "2351207d04fc16ade43ccab08600939c7c1fa70a5c0aaca76063d04c3228eaeb72",
+            "5d6d46ceed8f785ab9f2f9b06acfe398c6699c6129da084cb531177445a682894f",
+            "9685eaf836999221d17c9a64a3a057000524cd2823986db378b074290a1a9b93a2",
+            "2e135ed2c14c7e20c6d045cd00b903400374126676ea78874d79f2dd7883cf5c",
+        ],
+        [
+            "be2f5495c61cba1bb348a34948c004045e3bd4dae8f0fe82bf44d0da245a060048",
+            "eb5e68ce6dea1eb0229e144f578b3aa7e9f4f85febd135df8525e6fe40c6f0340d",
+            "13dd09b255ccd5112a94238f2be3c0b5b7ecde06580426a93e0708555a265305ab",
+            "f86d874e34b4995b788e37a823491f25127a502fe0704baa6bfdf04e76c13276",
+        ],
+        [
+            "2b166978cef14d9d438046c720519d8b1cad707e199746f1562d0c87fbd32940f0",
+            "e2545a96693a66654225ebbaac76d093bfa9cd8f525a53acb92a861a98c42e7d1c",
+            "4ae82e68ab691d510012edd2a728f98cd4794ef757e94d6546961b4f280a51aac3",
+            "39cc95b64a92b83cc3f26d8af8dfb4c091c240acdb4d47728d23e7148720ef04",
+        ],
+    ),
+    (
+        63,
+        [
+            "e9bc37a594daad83be9470df7f7b3798297c3d834ce80ba85d6e207627b7db7b11",
+            "97012b1e7d9af4d7cb7bdd1f3bb49a90a9b5dec3ea2bbc6eaebce77f4e470cbf46",
+            "87093b5352f04e4a4570fba233164e6acc36900e35d185886a827f7ea9bdc1e5c3",
+            "ce88b095a200e62c10c043b3e9bc6cb9b6ac4dfa51794b02ace9f98779040755",
+        ],
+        [
+            "bb1eb5d4afa793c1ebdd9fb08def6c36d10096986ae0cfe148cd101170ce37aea0",
+            "5a63d74a840aecd514f654f080e51ac50fd617d22610d91780fe6b07a26b0847ab",
+            "b38291058c97474ef6ddd190d30fc318185c09ca1589d2024f0a6f16d45f116783",
+            "77483fa5c005b2a107cb9943e5da634e7046855eaa888663de55d6471371d55d",
+        ],
+        [
+            "b6451e30b953c206e34644c6803724e9d2725e0893039cfc49584f991f451af3b8",
+            "9e8ff572d3da4f4022199b9563b9d70ebb616efff0763e9abec71b550f1371e233",
+            "319c4c4e74da936ba8e5bbb29a598e007a0bbfa929c99738ca2cc098d59134d11f",
+            "f300c39f82e2fce9f7f0fa266459503f64ab9913befc65fddc474f6dc1c67669",
+        ],
+    ),
+    (
+        64,
+        [
+            "4eed7141ea4a5cd4b788606bd23f46e212af9cacebacdc7d1f4c6dc7f2511b98fc",
+            "9cc56cb831ffe33ea8e7e1d1df09b26efd2767670066aa82d023b1dfe8ab1b2b7f",
+            "bb5b97592d46ffe3e05a6a9b592e2949c74160e4674301bc3f97e04903f8c6cf95",
+            "b863174c33228924cdef7ae47559b10b294acd660666c4538833582b43f82d74",
+        ],
+        [
+            "ba8ced36f327700d213f120b1a207a3b8c04330528586f414d09f2f7d9ccb7e682",
+            "44c26010afc3f762615bbac552a1ca909e67c83e2fd5478cf46b9e811efccc93f7",
+            "7a21b17a152ebaca1695733fdb086e23cd0eb48c41c034d52523fc21236e5d8c92",
+            "55306e48d52ba40b4dac24256460d56573d1312319afcf3ed39d72d0bfc69acb",
+        ],
+        [
+            "a5c4a7053fa86b64746d4bb688d06ad1f02a18fce9afd3e818fefaa7126bf73e9b",
+            "9493a9befebe0bf0c9509fb3105cfa0e262cde141aa8e3f2c2f77890bb64a4cca9",
+            "6922a21ead111f6338ad5244f2c15c44cb595443ac2ac294231e31be4a4307d0a9",
+            "1e874d36fc9852aeb1265c09b6e0cda7c37ef686fbbcab97e8ff66718be048bb",
+        ],
+    ),
+    (
+        65,
+        [
+            "de1e5fa0be70df6d2be8fffd0e99ceaa8eb6e8c93a63f2d8d1c30ecb6b263dee0e",
+            "16e0a4749d6811dd1d6d1265c29729b1b75a9ac346cf93f0e1d7296dfcfd4313b3",
+            "a227faaaaf7757cc95b4e87a49be3b8a270a12020233509b1c3632b3485eef309d",
+            "0abc4a4a696c9decc6e90454b53b000f456a3f10079072baaf7a981653221f2c",
+        ],
+        [
+            "c0a4edefa2d2accb9277c371ac12fcdbb52988a86edc54f0716e1591b4326e72d5",
+            "e795f46a596b02d3d4bfb43abad1e5d19211152722ec1f20fef2cd413e3c22f2fc",
+            "5da3d73041275be6ede3517b3b9f0fc67ade5956a672b8b75d96cb43294b904149",
+            "7de92637ed3f2439225e683910cb3ae923374449ca788fb0f9bea92731bc26ad",
+        ],
+        [
+            "51fd05c3c1cfbc8ed67d139ad76f5cf8236cd2acd26627a30c104dfd9d3ff8a82b",
+            "02e8bd36d8498a75ad8c8e9b15eb386970283d6dd42c8ae7911cc592887fdbe26a",
+            "0a5f0bf821cd92986c60b2502c9be3f98a9c133a7e8045ea867e0828c7252e7393",
+            "21f7c2d65daee4468eb4429efae469a42763f1f94977435d10dccae3e3dce88d",
+        ],
+    ),
+    (
+        127,
+        [
+            "d81293fda863f008c09e92fc382a81f5a0b4a1251cba1634016a0f86a6bd640de3",
+            "137d477156d1fde56b0cf36f8ef18b44b2d79897bece12227539ac9ae0a5119da4",
+            "7644d934d26e74dc316145dcb8bb69ac3f2e05c242dd6ee06484fcb0e956dc4435",
+            "5b452c5e2bbb5e2b66e99f5dd443d0cbcaaafd4beebaed24ae2f8bb672bcef78",
+        ],
+        [
+            "c64200ae7dfaf35577ac5a9521c47863fb71514a3bcad18819218b818de85818ee",
+            "7a317aaccc1458f78d6f65f3427ec97d9c0adb0d6dacd4471374b621b7b5f35cd5",
+            "4663c64dbe0b9e2d95632f84c611313ea5bd90b71ce97b3cf645776f3adc11e27d",
+            "135cbadb9875c2bf8d3ae6b02f8a0206aba0c35bfe42574011931c9a255ce6dc",
+        ],
+        [
+            "c91c090ceee3a3ac81902da31838012625bbcd73fcb92e7d7e56f78deba4f0c3fe",
+            "eb3974306966ccb3e3c69c337ef8a45660ad02526306fd685c88542ad00f759af6",
+            "dd1adc2e50c2b8aac9f0c5221ff481565cf6455b772515a69463223202e5c37174",
+            "3e35210bbbbabd89651684107fd9fe493c937be16e39cfa7084a36207c99bea3",
+        ],
+    ),
+    (
+        128,
+        [
+            "f17e570564b26578c33bb7f44643f539624b05df1a76c81f30acd548c44b45efa6",
+            "9faba091427f9c5c4caa873aa07828651f19c55bad85c47d1368b11c6fd99e47ec",
+            "ba5820a0325984d74fe3e4058494ca12e3f1d3293d0010a9722f7dee64f71246f7",
+            "5e9361f44cc8e214a100650db1313ff76a9f93ec6e84edb7add1cb4a95019b0c",
+        ],
+        [
+            "b04fe15577457267ff3b6f3c947d93be581e7e3a4b018679125eaf86f6a628ecd8",
+            "6bbe0001f10bda47e6077b735016fca8119da11348d93ca302bbd125bde0db2b50",
+            "edbe728a620bb9d3e6f706286aedea973425c0b9eedf8a38873544cf91badf49ad",
+            "92a635a93f71ddfcee1eae536c25d1b270956be16588ef1cfef2f1d15f650bd5",
+        ],
+        [
+            "81720f34452f58a0120a58b6b4608384b5c51d11f39ce97161a0c0e442ca022550",
+            "e7cd651e312f0b4c6afb3c348ae5dd17d2b29fab3b894d9a0034c7b04fd9190cbd",
+            "90043ff65d1657bbc05bfdecf2897dd894c7a1b54656d59a50b51190a9da44db42",
+            "6266ad6ce7c173a8c0bbe091b75e734b4dadb59b2861cd2518b4e7591e4b83c9",
+        ],
+    ),
+    (
+        129,
+        [
+            "683aaae9f3c5ba37eaaf072aed0f9e30bac0865137bae68b1fde4ca2aebdcb12f9",
+            "6ffa7b36dd78ba321be7e842d364a62a42e3746681c8bace18a4a8a79649285c71",
+            "27bf8febf125be9de39586d251f0d41da20980b70d35e3dac0eee59e468a894fa7",
+            "e6a07129aaad09855f6ad4801512a116ba2b7841e6cfc99ad77594a8f2d181a7",
+        ],
+        [
+            "d4a64dae6cdccbac1e5287f54f17c5f985105457c1a2ec1878ebd4b57e20d38f1c",
+            "9db018541eec241b748f87725665b7b1ace3e0065b29c3bcb232c90e37897fa5aa",
+            "ee7e1e8a2ecfcd9b51463e42238cfdd7fee1aecb3267fa7f2128079176132a412c",
+            "d8aaf0791276f6b98ff67359bd8652ef3a203976d5ff1cd41885573487bcd683",
+        ],
+        [
+            "938d2d4435be30eafdbb2b7031f7857c98b04881227391dc40db3c7b21f41fc18d",
+            "72d0f9c1de5760e1941aebf3100b51d64644cb459eb5d20258e233892805eb98b0",
+            "7570ef2a1787cd48e117c8d6a63a68fd8fc8e59e79dbe63129e88352865721c8d5",
+            "f0cf183f85e0609860472b0d6087cefdd186d984b21542c1c780684ed6832d8d",
+        ],
+    ),
+    (
+        1023,
+        [
+            "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11a1",
+            "82d27a591b05592b15607500e1e8dd56bc6c7fc063715b7a1d737df5bad3339c56",
+            "778957d870eb9717b57ea3d9fb68d1b55127bba6a906a4a24bbd5acb2d123a37b2",
+            "8f9e9a81bbaae360d58f85e5fc9d75f7c370a0cc09b6522d9c8d822f2f28f485",
+        ],
+        [
+            "c951ecdf03288d0fcc96ee3413563d8a6d3589547f2c2fb36d9786470f1b9d6e89",
+            "0316d2e6d8b8c25b0a5b2180f94fb1a158ef508c3cde45e2966bd796a696d3e13e",
+            "fd86259d756387d9becf5c8bf1ce2192b87025152907b6d8cc33d17826d8b7b9bc",
+            "97e38c3c85108ef09f013e01c229c20a83d9e8efac5b37470da28575fd755a10",
+        ],
+        [
+            "74a16c1c3d44368a86e1ca6df64be6a2f64cce8f09220787450722d85725dea59c",
+            "413264404661e9e4d955409dfe4ad3aa487871bcd454ed12abfe2c2b1eb7757588",
+            "cf6cb18d2eccad49e018c0d0fec323bec82bf1644c6325717d13ea712e6840d3e6",
+            "e730d35553f59eff5377a9c350bcc1556694b924b858f329c44ee64b884ef00d",
+        ],
+    ),
+    (
+        1024,
+        [
+            "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af71c",
+            "f8107265ecdaf8505b95d8fcec83a98a6a96ea5109d2c179c47a387ffbb404756f",
+            "6eeae7883b446b70ebb144527c2075ab8ab204c0086bb22b7c93d465efc57f8d91",
+            "7f0b385c6df265e77003b85102967486ed57db5c5ca170ba441427ed9afa684e",
+        ],
+        [
+            "75c46f6f3d9eb4f55ecaaee480db732e6c2105546f1e675003687c31719c7ba4a7",
+            "8bc838c72852d4f49c864acb7adafe2478e824afe51c8919d06168414c265f298a",
+            "8094b1ad813a9b8614acabac321f24ce61c5a5346eb519520d38ecc43e89b50002",
+            "36df0597243e4d2493fd626730e2ba17ac4d8824d09d1a4a8f57b8227778e2de",
+        ],
+        [
+            "7356cd7720d5b66b6d0697eb3177d9f8d73a4a5c5e968896eb6a6896843027066c",
+            "23b601d3ddfb391e90d5c8eccdef4ae2a264bce9e612ba15e2bc9d654af1481b2e",
+            "75dbabe615974f1070bba84d56853265a34330b4766f8e75edd1f4a1650476c108",
+            "02f22b64bd3919d246ba20a17558bc51c199efdec67e80a227251808d8ce5bad",
+        ],
+    ),
+    (
+        1025,
+        [
+            "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444f4",
+            "c4a22b4b399155358a994e52bf255de60035742ec71bd08ac275a1b51cc6bfe332",
+            "b0ef84b409108cda080e6269ed4b3e2c3f7d722aa4cdc98d16deb554e5627be8f9",
+            "55c98e1d5f9565a9194cad0c4285f93700062d9595adb992ae68ff12800ab67a",
+        ],
+        [
+            "357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea6936",
+            "2396b77fdc0d2634a552970843722066c3c15902ae5097e00ff53f1e116f1cd535",
+            "2720113a837ab2452cafbde4d54085d9cf5d21ca613071551b25d52e69d6c81123",
+            "872b6f19cd3bc1333edf0c52b94de23ba772cf82636cff4542540a7738d5b930",
+        ],
+        [
+            "effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb5d",
+            "31013a167509e9066273ab6e2123bc835b408b067d88f96addb550d96b6852dad3",
+            "8e320b9d940f86db74d398c770f462118b35d2724efa13da97194491d96dd37c3c",
+            "09cbef665953f2ee85ec83d88b88d11547a6f911c8217cca46defa2751e7f3ad",
+        ],
+    ),
+    (
+        2048,
+        [
+            "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a9a",
+            "60bf80001410ec9eea6698cd537939fad4749edd484cb541aced55cd9bf54764d0",
+            "63f23f6f1e32e12958ba5cfeb1bf618ad094266d4fc3c968c2088f677454c288c6",
+            "7ba0dba337b9d91c7e1ba586dc9a5bc2d5e90c14f53a8863ac75655461cea8f9",
+        ],
+        [
+            "879cf1fa2ea0e79126cb1063617a05b6ad9d0b696d0d757cf053439f60a99dd101",
+            "73b961cd574288194b23ece278c330fbb8585485e74967f31352a8183aa782b2b2",
+            "2f26cdcadb61eed1a5bc144b8198fbb0c13abbf8e3192c145d0a5c21633b0ef860",
+            "54f42809df823389ee40811a5910dcbd1018af31c3b43aa55201ed4edaac74fe",
+        ],
+        [
+            "7b2945cb4fef70885cc5d78a87bf6f6207dd901ff239201351ffac04e1088a23e2",
+            "c11a1ebffcea4d80447867b61badb1383d842d4e79645d48dd82ccba290769caa7",
+            "af8eaa1bd78a2a5e6e94fbdab78d9c7b74e894879f6a515257ccf6f95056f4e253",
+            "90f24f6b35ffbb74b766202569b1d797f2d4bd9d17524c720107f985f4ddc583",
+        ],
+    ),
+    (
+        2049,
+        [
+            "5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b687952256303096",
+            "de31d71d74103403822a2e0bc1eb193e7aecc9643a76b7bbc0c9f9c52e8783aae9",
+            "8764ca468962b5c2ec92f0c74eb5448d519713e09413719431c802f948dd5d9042",
+            "5a4ecdadece9eb178d80f26efccae630734dff63340285adec2aed3b51073ad3",
+        ],
+        [
+            "9f29700902f7c86e514ddc4df1e3049f258b2472b6dd5267f61bf13983b78dd5f9",
+            "a88abfefdfa1e00b418971f2b39c64ca621e8eb37fceac57fd0c8fc8e117d43b81",
+            "447be22d5d8186f8f5919ba6bcc6846bd7d50726c06d245672c2ad4f61702c6464",
+            "99ee1173daa061ffe15bf45a631e2946d616a4c345822f1151284712f76b2b0e",
+        ],
+        [
+            "2ea477c5515cc3dd606512ee72bb3e0e758cfae7232826f35fb98ca1bcbdf27316",
+            "d8e9e79081a80b046b60f6a263616f33ca464bd78d79fa18200d06c7fc9bffd808",
+            "cc4755277a7d5e09da0f29ed150f6537ea9bed946227ff184cc66a72a5f8c1e4bd",
+            "8b04e81cf40fe6dc4427ad5678311a61f4ffc39d195589bdbc670f63ae70f4b6",
+        ],
+    ),
+    (
+        3072,
+        [
+            "b98cb0ff3623be03326b373de6b9095218513e64f1ee2edd2525c7ad1e5cffd29a",
+            "3f6b0b978d6608335c09dc94ccf682f9951cdfc501bfe47b9c9189a6fc7b404d12",
+            "0258506341a6d802857322fbd20d3e5dae05b95c88793fa83db1cb08e7d8008d15",
+            "99b6209d78336e24839724c191b2a52a80448306e0daa84a3fdb566661a37e11",
+        ],
+        [
+            "044a0e7b172a312dc02a4c9a818c036ffa2776368d7f528268d2e6b5df19177022",
+            "f302d0529e4174cc507c463671217975e81dab02b8fdeb0d7ccc7568dd22574c78",
+            "3a76be215441b32e91b9a904be8ea81f7a0afd14bad8ee7c8efc305ace5d3dd61b",
+            "996febe8da4f56ca0919359a7533216e2999fc87ff7d8f176fbecb3d6f34278b",
+        ],
+        [
+            "050df97f8c2ead654d9bb3ab8c9178edcd902a32f8495949feadcc1e0480c46b36",
+            "04131bbd6e3ba573b6dd682fa0a63e5b165d39fc43a625d00207607a2bfeb65ff1",
+            "d29292152e26b298868e3b87be95d6458f6f2ce6118437b632415abe6ad522874b",
+            "cd79e4030a5e7bad2efa90a7a7c67e93f0a18fb28369d0a9329ab5c24134ccb0",
+        ],
+    ),
+    (
+        3073,
+        [
+            "7124b49501012f81cc7f11ca069ec9226cecb8a2c850cfe644e327d22d3e1cd39a",
+            "27ae3b79d68d89da9bf25bc27139ae65a324918a5f9b7828181e52cf373c84f35b",
+            "639b7fccbb985b6f2fa56aea0c18f531203497b8bbd3a07ceb5926f1cab74d14bd",
+            "66486d9a91eba99059a98bd1cd25876b2af5a76c3e9eed554ed72ea952b603bf",
+        ],
+        [
+            "68dede9bef00ba89e43f31a6825f4cf433389fedae75c04ee9f0cf16a427c95a96",
+            "d6da3fe985054d3478865be9a092250839a697bbda74e279e8a9e69f0025e4cfdd",
+            "d6cfb434b1cd9543aaf97c635d1b451a4386041e4bb100f5e45407cbbc24fa53ea",
+            "2de3536ccb329e4eb9466ec37093a42cf62b82903c696a93a50b702c80f3c3c5",
+        ],
+        [
+            "72613c9ec9ff7e40f8f5c173784c532ad852e827dba2bf85b2ab4b76f707908157",
+            "6288e552647a9d86481c2cae75c2dd4e7c5195fb9ada1ef50e9c5098c249d74392",
+            "9191441301c69e1f48505a4305ec1778450ee48b8e69dc23a25960fe33070ea549",
+            "119599760a8a2d28aeca06b8c5e9ba58bc19e11fe57b6ee98aa44b2a8e6b14a5",
+        ],
+    ),
+    (
+        4096,
+        [
+            "015094013f57a5277b59d8475c0501042c0b642e531b0a1c8f58d2163229e96902",
+            "89e9409ddb1b99768eafe1623da896faf7e1114bebeadc1be30829b6f8af707d85",
+            "c298f4f0ff4d9438aef948335612ae921e76d411c3a9111df62d27eaf871959ae0",
+            "062b5492a0feb98ef3ed4af277f5395172dbe5c311918ea0074ce0036454f620",
+        ],
+        [
+            "befc660aea2f1718884cd8deb9902811d332f4fc4a38cf7c7300d597a081bfc0bb",
+            "b64a36edb564e01e4b4aaf3b060092a6b838bea44afebd2deb8298fa562b7b597c",
+            "757b9df4c911c3ca462e2ac89e9a787357aaf74c3b56d5c07bc93ce899568a3eb1",
+            "7d9250c20f6c5f6c1e792ec9a2dcb715398d5a6ec6d5c54f586a00403a1af1de",
+        ],
+        [
+            "1e0d7f3db8c414c97c6307cbda6cd27ac3b030949da8e23be1a1a924ad2f25b9d7",
+            "8038f7b198596c6cc4a9ccf93223c08722d684f240ff6569075ed81591fd93f9ff",
+            "f1110b3a75bc67e426012e5588959cc5a4c192173a03c00731cf84544f65a2fb93",
+            "78989f72e9694a6a394a8a30997c2e67f95a504e631cd2c5f55246024761b245",
+        ],
+    ),
+    (
+        4097,
+        [
+            "9b4052b38f1c5fc8b1f9ff7ac7b27cd242487b3d890d15c96a1c25b8aa0fb99505",
+            "f91b0b5600a11251652eacfa9497b31cd3c409ce2e45cfe6c0a016967316c426bd",
+            "26f619eab5d70af9a418b845c608840390f361630bd497b1ab44019316357c61db",
+            "e091ce72fc16dc340ac3d6e009e050b3adac4b5b2c92e722cffdc46501531956",
+        ],
+        [
+            "00df940cd36bb9fa7cbbc3556744e0dbc8191401afe70520ba292ee3ca80abbc60",
+            "6db4976cfdd266ae0abf667d9481831ff12e0caa268e7d3e57260c0824115a54ce",
+            "595ccc897786d9dcbf495599cfd90157186a46ec800a6763f1c59e36197e9939e9",
+            "00809f7077c102f888caaf864b253bc41eea812656d46742e4ea42769f89b83f",
+        ],
+        [
+            "aca51029626b55fda7117b42a7c211f8c6e9ba4fe5b7a8ca922f34299500ead8a8",
+            "97f66a400fed9198fd61dd2d58d382458e64e100128075fc54b860934e8de2e841",
+            "70734b06e1d212a117100820dbc48292d148afa50567b8b84b1ec336ae10d40c8c",
+            "975a624996e12de31abbe135d9d159375739c333798a80c64ae895e51e22f3ad",
+        ],
+    ),
+    (
+        5120,
+        [
+            "9cadc15fed8b5d854562b26a9536d9707cadeda9b143978f319ab34230535833ac",
+            "c61c8fdc114a2010ce8038c853e121e1544985133fccdd0a2d507e8e615e611e9a",
+            "0ba4f47915f49e53d721816a9198e8b30f12d20ec3689989175f1bf7a300eee0d9",
+            "321fad8da232ece6efb8e9fd81b42ad161f6b9550a069e66b11b40487a5f5059",
+        ],
+        [
+            "2c493e48e9b9bf31e0553a22b23503c0a3388f035cece68eb438d22fa1943e209b",
+            "4dc9209cd80ce7c1f7c9a744658e7e288465717ae6e56d5463d4f80cdb2ef56495",
+            "f6a4f5487f69749af0c34c2cdfa857f3056bf8d807336a14d7b89bf62bef2fb54f",
+            "9af6a546f818dc1e98b9e07f8a5834da50fa28fb5874af91bf06020d1bf0120e",
+        ],
+        [
+            "7a7acac8a02adcf3038d74cdd1d34527de8a0fcc0ee3399d1262397ce5817f6055",
+            "d0cefd84d9d57fe792d65a278fd20384ac6c30fdb340092f1a74a92ace99c482b2",
+            "8f0fc0ef3b923e56ade20c6dba47e49227166251337d80a037e987ad3a7f728b5a",
+            "b6dfafd6e2ab1bd583a95d9c895ba9c2422c24ea0f62961f0dca45cad47bfa0d",
+        ],
+    ),
+    (
+        5121,
+        [
+            "628bd2cb2004694adaab7bbd778a25df25c47b9d4155a55f8fbd79f2fe154cff96",
+            "adaab0613a6146cdaabe498c3a94e529d3fc1da2bd08edf54ed64d40dcd6777647",
+            "eac51d8277d70219a9694334a68bc8f0f23e20b0ff70ada6f844542dfa32cd4204",
+            "ca1846ef76d811cdb296f65e260227f477aa7aa008bac878f72257484f2b6c95",
+        ],
+        [
+            "6ccf1c34753e7a044db80798ecd0782a8f76f33563accaddbfbb2e0ea4b2d0240d",
+            "07e63f13667a8d1490e5e04f13eb617aea16a8c8a5aaed1ef6fbde1b0515e3c810",
+            "50b361af6ead126032998290b563e3caddeaebfab592e155f2e161fb7cba939092",
+            "133f23f9e65245e58ec23457b78a2e8a125588aad6e07d7f11a85b88d375b72d",
+        ],
+        [
+            "b07f01e518e702f7ccb44a267e9e112d403a7b3f4883a47ffbed4b48339b3c341a",
+            "0add0ac032ab5aaea1e4e5b004707ec5681ae0fcbe3796974c0b1cf31a194740c1",
+            "4519273eedaabec832e8a784b6e7cfc2c5952677e6c3f2c3914454082d7eb1ce17",
+            "66ac7d75a4d3001fc89544dd46b5147382240d689bbbaefc359fb6ae30263165",
+        ],
+    ),
+    (
+        6144,
+        [
+            "3e2e5b74e048f3add6d21faab3f83aa44d3b2278afb83b80b3c35164ebeca2054d",
+            "742022da6fdda444ebc384b04a54c3ac5839b49da7d39f6d8a9db03deab32aade1",
+            "56c1c0311e9b3435cde0ddba0dce7b26a376cad121294b689193508dd63151603c",
+            "6ddb866ad16c2ee41585d1633a2cea093bea714f4c5d6b903522045b20395c83",
+        ],
+        [
+            "3d6b6d21281d0ade5b2b016ae4034c5dec10ca7e475f90f76eac7138e9bc8f1dc3",
+            "5754060091dc5caf3efabe0603c60f45e415bb3407db67e6beb3d11cf8e4f79075",
+            "61f05dace0c15807f4b5f389c841eb114d81a82c02a00b57206b1d11fa6e803486",
+            "b048a5ce87105a686dee041207e095323dfe172df73deb8c9532066d88f9da7e",
+        ],
+        [
+            "2a95beae63ddce523762355cf4b9c1d8f131465780a391286a5d01abb5683a1597",
+            "099e3c6488aab6c48f3c15dbe1942d21dbcdc12115d19a8b8465fb54e9053323a9",
+            "178e4275647f1a9927f6439e52b7031a0b465c861a3fc531527f7758b2b888cf2f",
+            "20582e9e2c593709c0a44f9c6e0f8b963994882ea4168827823eef1f64169fef",
+        ],
+    ),
+    (
+        6145,
+        [
+            "f1323a8631446cc50536a9f705ee5cb619424d46887f3c376c695b70e0f0507f18",
+            "a2cfdd73c6e39dd75ce7c1c6e3ef238fd54465f053b25d21044ccb2093beb01501",
+            "5532b108313b5829c3621ce324b8e14229091b7c93f32db2e4e63126a377d2a63a",
+            "3597997d4f1cba59309cb4af240ba70cebff9a23d5e3ff0cdae2cfd54e070022",
+        ],
+        [
+            "9ac301e9e39e45e3250a7e3b3df701aa0fb6889fbd80eeecf28dbc6300fbc539f3",
+            "c184ca2f59780e27a576c1d1fb9772e99fd17881d02ac7dfd39675aca918453283",
+            "ed8c3169085ef4a466b91c1649cc341dfdee60e32231fc34c9c4e0b9a2ba87ca8f",
+            "372589c744c15fd6f985eec15e98136f25beeb4b13c4e43dc84abcc79cd4646c",
+        ],
+        [
+            "379bcc61d0051dd489f686c13de00d5b14c505245103dc040d9e4dd1facab8e511",
+            "4493d029bdbd295aaa744a59e31f35c7f52dba9c3642f773dd0b4262a9980a2aef",
+            "811697e1305d37ba9d8b6d850ef07fe41108993180cf779aeece363704c7648345",
+            "8603bbeeb693cffbbe5588d1f3535dcad888893e53d977424bb707201569a8d2",
+        ],
+    ),
+    (
+        7168,
+        [
+            "61da957ec2499a95d6b8023e2b0e604ec7f6b50e80a9678b89d2628e99ada77a57",
+            "07c321c83361793b9af62a40f43b523df1c8633cecb4cd14d00bdc79c78fca5165",
+            "b863893f6d38b02ff7236c5a9a8ad2dba87d24c547cab046c29fc5bc1ed142e1de",
+            "4763613bb162a5a538e6ef05ed05199d751f9eb58d332791b8d73fb74e4fce95",
+        ],
+        [
+            "b42835e40e9d4a7f42ad8cc04f85a963a76e18198377ed84adddeaecacc6f3fca2",
+            "f01d5277d69bb681c70fa8d36094f73ec06e452c80d2ff2257ed82e7ba34840098",
+            "9a65ee8daa7094ae0933e3d2210ac6395c4af24f91c2b590ef87d7788d7066ea3e",
+            "aebca4c08a4f14b9a27644f99084c3543711b64a070b94f2c9d1d8a90d035d52",
+        ],
+        [
+            "11c37a112765370c94a51415d0d651190c288566e295d505defdad895dae223730",
+            "d5a5175a38841693020669c7638f40b9bc1f9f39cf98bda7a5b54ae24218a800a2",
+            "116b34665aa95d846d97ea988bfcb53dd9c055d588fa21ba78996776ea6c40bc42",
+            "8b53c62b5f3ccf200f647a5aae8067f0ea1976391fcc72af1945100e2a6dcb88",
+        ],
+    ),
+    (
+        7169,
+        [
+            "a003fc7a51754a9b3c7fae0367ab3d782dccf28855a03d435f8cfe74605e781798",
+            "a8b20534be1ca9eb2ae2df3fae2ea60e48c6fb0b850b1385b5de0fe460dbe9d9f9",
+            "b0d8db4435da75c601156df9d047f4ede008732eb17adc05d96180f8a735485228",
+            "40779e6062d643b79478a6e8dbce68927f36ebf676ffa7d72d5f68f050b119c8",
+        ],
+        [
+            "ed9b1a922c046fdb3d423ae34e143b05ca1bf28b710432857bf738bcedbfa5113c",
+            "9e28d72fcbfc020814ce3f5d4fc867f01c8f5b6caf305b3ea8a8ba2da3ab69fabc",
+            "b438f19ff11f5378ad4484d75c478de425fb8e6ee809b54eec9bdb184315dc8566",
+            "17c09f5340451bf42fd3270a7b0b6566169f242e533777604c118a6358250f54",
+        ],
+        [
+            "554b0a5efea9ef183f2f9b931b7497995d9eb26f5c5c6dad2b97d62fc5ac31d99b",
+            "20652c016d88ba2a611bbd761668d5eda3e568e940faae24b0d9991c3bd25a65f7",
+            "70b89fdcadabcb3d1a9c1cb63e69721cacf1ae69fefdcef1e3ef41bc5312ccc172",
+            "22199e47a26552c6adc460cf47a72319cb5039369d0060eaea59d6c65130f1dd",
+        ],
+    ),
+    (
+        8192,
+        [
+            "aae792484c8efe4f19e2ca7d371d8c467ffb10748d8a5a1ae579948f718a2a635f",
+            "e51a27db045a567c1ad51be5aa34c01c6651c4d9b5b5ac5d0fd58cf18dd61a4777",
+            "8566b797a8c67df7b1d60b97b19288d2d877bb2df417ace009dcb0241ca1257d62",
+            "712b6a4043b4ff33f690d849da91ea3bf711ed583cb7b7a7da2839ba71309bbf",
+        ],
+        [
+            "dc9637c8845a770b4cbf76b8daec0eebf7dc2eac11498517f08d44c8fc00d58a48",
+            "34464159dcbc12a0ba0c6d6eb41bac0ed6585cabfe0aca36a375e6c5480c22afdc",
+            "40785c170f5a6b8a1107dbee282318d00d915ac9ed1143ad40765ec120042ee121",
+            "cd2baa36250c618adaf9e27260fda2f94dea8fb6f08c04f8f10c78292aa46102",
+        ],
+        [
+            "ad01d7ae4ad059b0d33baa3c01319dcf8088094d0359e5fd45d6aeaa8b2d0c3d4c",
+            "9e58958553513b67f84f8eac653aeeb02ae1d5672dcecf91cd9985a0e67f450191",
+            "0ecba25555395427ccc7241d70dc21c190e2aadee875e5aae6bf1912837e53411d",
+            "abf7a56cbf8e4fb780432b0d7fe6cec45024a0788cf5874616407757e9e6bef7",
+        ],
+    ),
+    (
+        8193,
+        [
+            "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3bb2",
+            "282aa69be089359ea1154b9a9286c4a56af4de975a9aa4a5c497654914d279bea6",
+            "0bb6d2cf7225a2fa0ff5ef56bbe4b149f3ed15860f78b4e2ad04e158e375c1e0c0",
+            "b551cd7dfc82f1b155c11b6b3ed51ec9edb30d133653bb5709d1dbd55f4e1ff6",
+        ],
+        [
+            "954a2a75420c8d6547e3ba5b98d963e6fa6491addc8c023189cc519821b4a1f5f0",
+            "3228648fd983aef045c2fa8290934b0866b615f585149587dda229903996532883",
+            "5a2b18f1d63b7e300fc76ff260b571839fe44876a4eae66cbac8c67694411ed7e0",
+            "9df51068a22c6e67d6d3dd2cca8ff12e3275384006c80f4db68023f24eebba57",
+        ],
+        [
+            "af1e0346e389b17c23200270a64aa4e1ead98c61695d917de7d5b00491c9b0f12f",
+            "20a01d6d622edf3de026a4db4e4526225debb93c1237934d71c7340bb5916158cb",
+            "dafe9ac3225476b6ab57a12357db3abbad7a26c6e66290e44034fb08a20a8d0ec2",
+            "64f309994d2810c49cfba6989d7abb095897459f5425adb48aba07c5fb3c83c0",
+        ],
+    ),
+    (
+        16384,
+        [
+            "f875d6646de28985646f34ee13be9a576fd515f76b5b0a26bb324735041ddde49d",
+            "764c270176e53e97bdffa58d549073f2c660be0e81293767ed4e4929f9ad34bbb3",
+            "9a529334c57c4a381ffd2a6d4bfdbf1482651b172aa883cc13408fa67758a3e475",
+            "03f93f87720a3177325f7823251b85275f64636a8f1d599c2e49722f42e93893",
+        ],
+        [
+            "9e9fc4eb7cf081ea7c47d1807790ed211bfec56aa25bb7037784c13c4b707b0df9",
+            "e601b101e4cf63a404dfe50f2e1865bb12edc8fca166579ce0c70dba5a5c0fc960",
+            "ad6f3772183416a00bd29d4c6e651ea7620bb100c9449858bf14e1ddc9ecd35725",
+            "581ca5b9160de04060045993d972571c3e8f71e9d0496bfa744656861b169d65",
+        ],
+        [
+            "160e18b5878cd0df1c3af85eb25a0db5344d43a6fbd7a8ef4ed98d0714c3f7e160",
+            "dc0b1f09caa35f2f417b9ef309dfe5ebd67f4c9507995a531374d099cf8ae31754",
+            "2e885ec6f589378864d3ea98716b3bbb65ef4ab5e0ab5bb298a501f19a41ec19af",
+            "84a5e6b428ecd813b1a47ed91c9657c3fba11c406bc316768b58f6802c9e9b57",
+        ],
+    ),
+    (
+        31744,
+        [
+            "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c4786",
+            "0cc51f2b0c28a7b77304bd55fe73af663c02d3f52ea053ba43431ca5bab7bfea2f",
+            "5e9d7121770d88f70ae9649ea713087d1914f7f312147e247f87eb2d4ffef0ac97",
+            "8bf7b6579d57d533355aa20b8b77b13fd09748728a5cc327a8ec470f4013226f",
+        ],
+        [
+            "efa53b389ab67c593dba624d898d0f7353ab99e4ac9d42302ee64cbf9939a4193a",
+            "7258db2d9cd32a7a3ecfce46144114b15c2fcb68a618a976bd74515d47be08b628",
+            "be420b5e830fade7c080e351a076fbc38641ad80c736c8a18fe3c66ce12f95c61c",
+            "2462a9770d60d0f77115bbcd3782b593016a4e728d4c06cee4505cb0c08a42ec",
+        ],
+        [
+            "39772aef80e0ebe60596361e45b061e8f417429d529171b6764468c22928e28e97",
+            "59adeb797a3fbf771b1bcea30150a020e317982bf0d6e7d14dd9f064bc11025c25",
+            "f31e81bd78a921db0174f03dd481d30e93fd8e90f8b2fee209f849f2d2a52f3171",
+            "9a490fb0ba7aea1e09814ee912eba111a9fde9d5c274185f7bae8ba85d300a2b",
+        ],
+    ),
+    (
+        102400,
+        [
+            "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085e0",
+            "1c59dab908c04c3342b816941a26d69c2605ebee5ec5291cc55e15b76146e6745f",
+            "0601156c3596cb75065a9c57f35585a52e1ac70f69131c23d611ce11ee4ab1ec2c",
+            "009012d236648e77be9295dd0426f29b764d65de58eb7d01dd42248204f45f8e",
+        ],
+        [
+            "1c35d1a5811083fd7119f5d5d1ba027b4d01c0c6c49fb6ff2cf75393ea5db4a7f9",
+            "dbdd3e1d81dcbca3ba241bb18760f207710b751846faaeb9dff8262710999a59b2",
+            "aa1aca298a032d94eacfadf1aa192418eb54808db23b56e34213266aa08499a16b",
+            "354f018fc4967d05f8b9d2ad87a7278337be9693fc638a3bfdbe314574ee6fc4",
+        ],
+        [
+            "4652cff7a3f385a6103b5c260fc1593e13c778dbe608efb092fe7ee69df6e9c6d8",
+            "3a3e041bc3a48df2879f4a0a3ed40e7c961c73eff740f3117a0504c2dff4786d44",
+            "fb17f1549eb0ba585e40ec29bf7732f0b7e286ff8acddc4cb1e23b87ff5d824a98",
+            "6458dcc6a04ac83969b80637562953df51ed1a7e90a7926924d2763778be8560",
+        ],
+    ),
+];
+
+// Bao's published vectors: input is a little-endian u32 counter starting at
+// 1, with the BLAKE3 hash (the Bao root) and the hash of the full encoding.
+const BAO_SELF_TEST_VECTORS: [(usize, &str, &str); 13] = [
+    (
+        0,
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+        "71e0a99173564931c0b8acc52d2685a8e39c64dc52e3d02390fdac2a12b155cb",
+    ),
+    (
+        1,
+        "48fc721fbbc172e0925fa27af1671de225ba927134802998b10a1568a188652b",
+        "fa1fd2786e8860a7aa94276683579b3ed999ebdc2257a924811c4bcdbe5ee9f4",
+    ),
+    (
+        1023,
+        "15f8c1ae1049fe7e837186612c8ce732e66835841a4569b71e4ac3e3d3411b90",
+        "94c16da9b8aab7077c49f73658b3d522c55f5cf94f9a22e1c91f7e2e75953803",
+    ),
+    (
+        1024,
+        "f749c19181983b839cd97fe121cebaf076bc951e8c8e6d64accfedad5951ec22",
+        "62881f0fbd8b62d69f23b75abe62f4c56874a58699ff6741686f40dfcc20f05e",
+    ),
+    (
+        1025,
+        "3613596275c4ea790774dedf20835b2daf86cacc892feef6ce720c121572f1f9",
+        "04a7fc9414f25fbb4529968d4eb32e569691ad3517f45fa736cfddaed99d66f5",
+    ),
+    (
+        2047,
+        "89cc9b9cf2a83f03b22983c8bd7f2df392f5c1966f0e221db9ab396f7043c4d7",
+        "7b74fc273e48f75185f87e9574edcf286eb267be463c056c93c39728b18d250d",
+    ),
+    (
+        2048,
+        "fed8b40d6095dc7c5061f9cd832fd192337473bd392bf6f6bbaf1261ea78f8fa",
+        "d1c5ba94d24e1e3005685e53521b6110c1b16cb675f8c1a6ba95d3cca111fb15",
+    ),
+    (
+        2049,
+        "64770fa15a4bbe7770654c4ac68ed4f0e975ad6c85b5edb4d3db3b4b604e084e",
+        "d9ea16809c9a8a86481b09799c8a71e58a1f48a9eec2f85a17a61005229c0de8",
+    ),
+    (
+        3071,
+        "3bf36194161a10a32843a84568d0ce0a524b3bc6182abc492ddaaa3dae785279",
+        "d5e69f5c15b39fd840f3e18cf732d5682d0327391178c7fc9f04ec6dcb83be52",
+    ),
+    (
+        3072,
+        "9748169f2aa70258d18cef6dc6b4b4511265e268e85f73dcbea6e34ab0341da1",
+        "954bd49159ed95edb871fc9d7bc5388ed3dc31e68e1347e999ecea8c0a9d7737",
+    ),
+    (
+        3073,
+        "5ba075072daba2470558a171e3769fba057dc3f12375c60892bdbe73348d9fd1",
+        "a49213d8e20de518bc871dd2d6e51569279b9f7639dc5f7fa6510c86fb7b8eba",
+    ),
+    (
+        11264,
+        "69fa39c8ab837dbb9e419c66a3ab7014ce9f86dba55935936b4c38cfcbc4d5f6",
+        "1bffa084b6811992e88fd0d57f0091de2fe883c4a54348149b741afeb266f874",
+    ),
+    (
+        13312,
+        "3e88d1dd20f426640077dcf82d6d4e18ee0062aa72f8ae547a0e65fcd36a0f06",
+        "e5ca844ba6ac49fad8f888b63b437d7d25ee15d80a7bc01edac16f78e2a65271",
+    ),
+];
+
+fn self_test_hex(output: &[u8], expected: SelfTestHex) -> bool {
+    hex_lower(output) == expected.concat()
+}
+
+// Hashes input as the subtree starting at offset, splitting it as the tree
+// does so every level goes through merge_subtrees_non_root.
+fn self_test_subtree(
+    new: &dyn Fn() -> blake3::Hasher,
+    input: &[u8],
+    offset: u64,
+    mode: Mode,
+) -> ChainingValue {
+    if input.len() <= CHUNK_LEN {
+        return new()
+            .set_input_offset(offset)
+            .update(input)
+            .finalize_non_root();
+    }
+    let left_len = hazmat::left_subtree_len(input.len() as u64);
+    let (left, right) = input.split_at(left_len as usize);
+    hazmat::merge_subtrees_non_root(
+        &self_test_subtree(new, left, offset, mode),
+        &self_test_subtree(new, right, offset + left_len, mode),
+        mode,
+    )
+}
+
+fn self_test_case(
+    new: &dyn Fn() -> blake3::Hasher,
+    input: &[u8],
+    mode: Mode,
+    expected: SelfTestHex,
+) -> bool {
+    let mut output = [0; SELF_TEST_XOF_LEN];
+    new().update(input).finalize_xof().fill(&mut output);
+    let one_shot = self_test_hex(&output, expected);
+    let mut hasher = new();
+    for piece in input.chunks(1000) {
+        hasher.update(piece);
+    }
+    hasher.finalize_xof().fill(&mut output);
+    let incremental = self_test_hex(&output, expected);
+    let merged = input.len() <= CHUNK_LEN || {
+        let left_len = hazmat::left_subtree_len(input.len() as u64);
+        let (left, right) = input.split_at(left_len as usize);
+        hazmat::merge_subtrees_root_xof(
+            &self_test_subtree(new, left, 0, mode),
+            &self_test_subtree(new, right, left_len, mode),
+            mode,
+        )
+        .fill(&mut output);
+        self_test_hex(&output, expected)
+    };
+    one_shot && incremental && merged
+}
+
+// The tree exports' path in all three modes: chunk CVs merged up to the
+// root as hash_chunks_batch and root_from_leaf_cvs compute them.
+#[cfg(feature = "hazmat")]
+fn self_test_tree(input: &[u8], expected: [SelfTestHex; 3]) -> bool {
+    let context_key = hazmat::hash_derive_key_context(SELF_TEST_CONTEXT);
+    let trees = [
+        TreeMode::Hash,
+        TreeMode::Keyed(Zeroizing::new(*SELF_TEST_KEY)),
+        TreeMode::DeriveKey(Zeroizing::new(context_key)),
+    ];
+    input.len() <= CHUNK_LEN
+        || trees.iter().zip(expected).all(|(tree, expected)| {
+            let cvs = input
+                .chunks(CHUNK_LEN)
+                .enumerate()
+                .map(|(i, chunk)| tree.subtree_cv(chunk, (i * CHUNK_LEN) as u64))
+                .collect();
+            let root = root_from_cvs(cvs, tree.mode());
+            expected.concat().starts_with(root.to_hex().as_str())
+        })
+}
+
+#[cfg(not(feature = "hazmat"))]
+fn self_test_tree(_input: &[u8], _expected: [SelfTestHex; 3]) -> bool {
+    true
+}
+
+// Checks the compiled module against known answers: official BLAKE3 vectors
+// in all three modes, through the one-shot, incremental and subtree paths
+// and the chunk CV exports' merge, plus Bao encoding and verified decoding.
+#[wasm_bindgen]
+pub fn self_test() -> bool {
+    let input: Vec<u8> = (0..102400).map(|i| (i % 251) as u8).collect();
+    let context_key = hazmat::hash_derive_key_context(SELF_TEST_CONTEXT);
+    let blake3_ok = SELF_TEST_VECTORS
+        .iter()
+        .all(|&(len, hash, keyed, derived)| {
+            let input = &input[..len];
+            self_test_tree(input, [hash, keyed, derived])
+                && self_test_case(&blake3::Hasher::new, input, Mode::Hash, hash)
+                && self_test_case(
+                    &|| blake3::Hasher::new_keyed(SELF_TEST_KEY),
+                    input,
+                    Mode::KeyedHash(SELF_TEST_KEY),
+                    keyed,
+                )
+                && self_test_case(
+                    &|| blake3::Hasher::new_derive_key(SELF_TEST_CONTEXT),
+                    input,
+                    Mode::DeriveKeyMaterial(&context_key),
+                    derived,
+                )
+        });
+    let bao_input: Vec<u8> = (1..=4096u32).flat_map(u32::to_le_bytes).collect();
+    blake3_ok
+        && BAO_SELF_TEST_VECTORS.iter().all(|&(len, hash, encoded)| {
+            let input = &bao_input[..len];
+            blake3::hash(input).to_hex().as_str() == hash && bao_self_test(input, encoded)
+        })
+}
+
+// Push sizes for the verifier checks: a byte at a time, a header-sized step,
+// odd sizes that split parents and chunks, and everything at once.
+#[cfg(feature = "bao")]
+const SELF_TEST_PUSH_LENS: [usize; 5] = [1, HEADER_LEN, PARENT_LEN + 1, CHUNK_LEN + 3, usize::MAX];
+
+// Pushes input to a verifier in pieces of piece_len, returning all output.
+#[cfg(feature = "bao")]
+fn self_test_push<F>(mut push: F, input: &[u8], piece_len: usize) -> Option<Vec<u8>>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>, JsError>,
+{
+    let mut out = Vec::new();
+    for piece in input.chunks(piece_len.min(input.len()).max(1)) {
+        out.extend(push(piece).ok()?);
+    }
+    Some(out)
+}
+
+// The hand-written VerifiedDecoder is checked against bao::decode, which
+// decodes the same encoding independently.
+#[cfg(feature = "bao")]
+fn bao_self_test(input: &[u8], encoded_hash: &str) -> bool {
+    let (encoded, root) = bao::encode::encode(input);
+    let Ok(reference) = bao::decode::decode(&encoded, &root) else {
+        return false;
+    };
+    root == blake3::hash(input)
+        && blake3::hash(&encoded).to_hex().as_str() == encoded_hash
+        && reference == input
+        && SELF_TEST_PUSH_LENS.iter().all(|&piece_len| {
+            let Ok(mut decoder) = VerifiedDecoder::new(root.as_bytes()) else {
+                return false;
+            };
+            self_test_push(|piece| decoder.push(piece), &encoded, piece_len)
+                .is_some_and(|out| out == reference)
+                && decoder.finish().is_ok()
+        })
+        && bao_slice_self_test(input, &encoded, &root)
+}
+
+// RangeVerifier's SliceVerifier against bao's SliceDecoder over ranges that
+// start and end mid-chunk, on chunk boundaries and past the end, with the
+// state saved and restored halfway through each run of pushes.
+#[cfg(feature = "bao")]
+fn bao_slice_self_test(input: &[u8], encoded: &[u8], root: &blake3::Hash) -> bool {
+    let len = input.len() as u64;
+    let chunk = CHUNK_LEN as u64;
+    let ranges = [
+        (0, 0),
+        (0, 1),
+        (0, u64::MAX),
+        (chunk - 1, 2),
+        (chunk, chunk),
+        (len / 3, len / 2),
+        (len.saturating_sub(1), 1),
+        (len, 10),
+        (len + chunk, 1),
+    ];
+    ranges.iter().all(|&(start, range_len)| {
+        let mut slice = Vec::new();
+        let mut reference = Vec::new();
+        let extracted = SliceExtractor::new(Cursor::new(encoded), start, range_len)
+            .read_to_end(&mut slice)
+            .is_ok();
+        let decoded = SliceDecoder::new(&*slice, root, start, range_len)
+            .read_to_end(&mut reference)
+            .is_ok();
+        extracted
+            && decoded
+            && SELF_TEST_PUSH_LENS.iter().all(|&piece_len| {
+                let Ok(mut verifier) = SliceVerifier::new(root.as_bytes(), start, range_len) else {
+                    return false;
+                };
+                let pieces: Vec<&[u8]> = slice.chunks(piece_len.min(slice.len()).max(1)).collect();
+                let (first, second) = pieces.split_at(pieces.len() / 2);
+                let mut out = Vec::new();
+                for piece in first {
+                    let Ok(piece_out) = verifier.push(piece) else {
+                        return false;
+                    };
+                    out.extend(piece_out);
+                }
+                let Ok(mut verifier) =
+                    SliceVerifier::restore_state(root.as_bytes(), &verifier.save_state())
+                else {
+                    return false;
+                };
+                for piece in second {
+                    let Ok(piece_out) = verifier.push(piece) else {
+                        return false;
+                    };
+                    out.extend(piece_out);
+                }
+                out == reference && verifier.finish().is_ok()
+            })
+    })
+}
+
+#[cfg(not(feature = "bao"))]
+fn bao_self_test(_input: &[u8], _encoded_hash: &str) -> bool {
+    true
+}
+
+// Logic that doesn't cross into JS; run natively with
+// cargo test --target <host triple>.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn b3sum_lines_with_double_spaces() {
+        let hex = blake3::hash(b"abc").to_hex();
+        for (line, name) in [
+            (format!("{hex}  a  b"), "a  b"),
+            (format!("{hex}    lead"), "  lead"),
+            (format!("BLAKE3 (a  b) = {hex}"), "a  b"),
+            (format!("BLAKE3 (x) = y) = {hex}"), "x) = y"),
+            (format!("\\{hex}  a\\nb  c"), "a\nb  c"),
+        ] {
+            let (digest, filename) = parse_b3sum_line(&line).unwrap();
+            assert_eq!(digest, blake3::hash(b"abc"));
+            assert_eq!(filename, name);
+        }
+        for line in [
+            format!("{hex} x"),
+            format!("{}  x", &hex[2..]),
+            format!("BLAKE3 (a  b) {hex}"),
+            format!("BLAKE3 (a) = {}", hex.to_uppercase()),
+            format!("x{}", "\u{e9}".repeat(40)),
+        ] {
+            assert!(parse_b3sum_line(&line).is_err(), "{line}");
+        }
+    }
+
+    // input byte i = i % 251, as in BLAKE3's test vectors.
+    fn test_input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[cfg(feature = "bao")]
+    const BAO_LENS: [usize; 9] = [0, 1, 1023, 1024, 1025, 3072, 5000, 16384, 70000];
+
+    #[cfg(feature = "bao")]
+    fn bao_slice(content: &[u8], outboard: Option<&[u8]>, start: u64, len: u64) -> Vec<u8> {
+        let mut slice = Vec::new();
+        match outboard {
+            Some(outboard) => SliceExtractor::new_outboard(
+                Cursor::new(content),
+                Cursor::new(outboard),
+                start,
+                len,
+            )
+            .read_to_end(&mut slice),
+            None => SliceExtractor::new(Cursor::new(content), start, len).read_to_end(&mut slice),
+        }
+        .unwrap();
+        slice
+    }
+
+    #[cfg(feature = "bao")]
+    fn slice_cases(n: u64) -> [(u64, u64); 6] {
+        [
+            (0, 0),
+            (0, 1),
+            (1500, 3000),
+            (n.saturating_sub(1), 1),
+            (n + 10, 5),
+            (0, u64::MAX),
+        ]
+    }
+
+    #[cfg(feature = "bao")]
+    #[test]
+    fn slice_plans_match_bao_slices() {
+        for n in BAO_LENS {
+            let input = test_input(n);
+            let (encoded, _) = bao::encode::encode(&input);
+            let (outboard, _) = bao::encode::outboard(&input);
+            for (start, len) in slice_cases(n as u64) {
+                for is_outboard in [false, true] {
+                    let mut planned = Vec::new();
+                    for range in SlicePlan::ranges(n as u64, start, len, is_outboard) {
+                        let source = match range.source {
+                            "content" => &input,
+                            "outboard" => &outboard,
+                            _ => &encoded,
+                        };
+                        planned
+                            .extend_from_slice(&source[range.start as usize..range.end as usize]);
+                    }
+                    let expected = if is_outboard {
+                        bao_slice(&input, Some(&outboard), start, len)
+                    } else {
+                        bao_slice(&encoded, None, start, len)
+                    };
+                    assert_eq!(planned, expected, "{n} {start} {len} {is_outboard}");
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "bao")]
+    #[test]
+    fn slice_verifier_decodes_bao_encodings_and_slices() {
+        for n in BAO_LENS {
+            let input = test_input(n);
+            let (encoded, root) = bao::encode::encode(&input);
+            assert_eq!(root, blake3::hash(&input));
+            for piece_len in [
+                1,
+                HEADER_LEN,
+                PARENT_LEN + 1,
+                CHUNK_LEN + 3,
+                encoded.len().max(1),
+            ] {
+                let mut decoder = SliceVerifier::new(root.as_bytes(), 0, u64::MAX).unwrap();
+                let mut out = Vec::new();
+                for piece in encoded.chunks(piece_len) {
+                    out.extend(decoder.push(piece).unwrap());
+                }
+                decoder.finish().unwrap();
+                assert_eq!(out, input, "{n} {piece_len}");
+            }
+            for (start, len) in slice_cases(n as u64) {
+                let slice = bao_slice(&encoded, None, start, len);
+                let from = (start as usize).min(n);
+                let to = start.saturating_add(len).min(n as u64) as usize;
+                // Saved halfway and restored, as a resumed download would.
+                let mut verifier = SliceVerifier::new(root.as_bytes(), start, len).unwrap();
+                let (head, tail) = slice.split_at(slice.len() / 2);
+                let mut out = verifier.push(head).unwrap();
+                let mut verifier =
+                    SliceVerifier::restore_state(root.as_bytes(), &verifier.save_state()).unwrap();
+                out.extend(verifier.push(tail).unwrap());
+                verifier.finish().unwrap();
+                assert_eq!(out, &input[from..to.max(from)], "{n} {start} {len}");
+            }
+        }
+    }
+
+    #[cfg(feature = "bao")]
+    #[test]
+    fn outboard_checks_match_bao_outboards() {
+        for n in BAO_LENS {
+            let input = test_input(n);
+            let (mut outboard, root) = bao::encode::outboard(&input);
+            let total_len = n as u64;
+            assert_eq!(outboard_mismatch(&outboard, root, total_len), None);
+            for (i, chunk) in input.chunks(CHUNK_LEN).enumerate() {
+                let offset = (i * CHUNK_LEN) as u64;
+                assert!(outboard_chunk_matches(
+                    chunk, offset, &outboard, root, total_len
+                ));
+                let mut bad = chunk.to_vec();
+                bad[0] ^= 1;
+                assert!(!outboard_chunk_matches(
+                    &bad, offset, &outboard, root, total_len
+                ));
+            }
+            // Each parent is checked, in the pre-order it is stored in.
+            for pos in (HEADER_LEN..outboard.len()).step_by(PARENT_LEN) {
+                outboard[pos + 7] ^= 1;
+                assert_eq!(outboard_mismatch(&outboard, root, total_len), Some(pos));
+                let matches = |(i, chunk): (usize, &[u8])| {
+                    outboard_chunk_matches(
+                        chunk,
+                        (i * CHUNK_LEN) as u64,
+                        &outboard,
+                        root,
+                        total_len,
+                    )
+                };
+                assert!(!input.chunks(CHUNK_LEN).enumerate().all(matches));
+                outboard[pos + 7] ^= 1;
+            }
+        }
+    }
+
+    #[cfg(feature = "hazmat")]
+    #[test]
+    fn chunk_cvs_merge_to_blake3_roots() {
+        let context_key = hazmat::hash_derive_key_context(SELF_TEST_CONTEXT);
+        for n in [1025, 2048, 3072, 5000, 8192, 65 * 1024 + 1, 102400] {
+            let input = test_input(n);
+            for (tree, expected) in [
+                (TreeMode::Hash, blake3::hash(&input)),
+                (
+                    TreeMode::Keyed(Zeroizing::new(*SELF_TEST_KEY)),
+                    blake3::keyed_hash(SELF_TEST_KEY, &input),
+                ),
+                (
+                    TreeMode::DeriveKey(Zeroizing::new(context_key)),
+                    blake3::Hasher::new_derive_key(SELF_TEST_CONTEXT)
+                        .update(&input)
+                        .finalize(),
+                ),
+            ] {
+                let cvs: Vec<ChainingValue> = input
+                    .chunks(CHUNK_LEN)
+                    .enumerate()
+                    .map(|(i, chunk)| tree.subtree_cv(chunk, (i * CHUNK_LEN) as u64))
+                    .collect();
+                assert_eq!(root_from_cvs(cvs.clone(), tree.mode()), expected, "{n}");
+                // The left half of the root split is one subtree of its own.
+                let left_len = hazmat::left_subtree_len(n as u64) as usize;
+                let mut left = cvs[..left_len / CHUNK_LEN].to_vec();
+                while left.len() > 1 {
+                    left = merge_cv_level(&left, tree.mode());
+                }
+                assert_eq!(left[0], tree.subtree_cv(&input[..left_len], 0));
+                let right = tree.subtree_cv(&input[left_len..], left_len as u64);
+                assert_eq!(
+                    hazmat::merge_subtrees_root(&left[0], &right, tree.mode()),
+                    expected
+                );
+                // Pushed as subtree spans, a CvStack in the same mode gets there too.
+                let mut spans = Vec::new();
+                push_subtree_spans(0, n as u64, 3, &mut spans);
+                let mut stack = CvStack::default();
+                stack.tree = tree;
+                for span in &spans {
+                    let (offset, len) = (span.input_offset, span.length);
+                    assert!(check_subtree(offset, len).is_ok());
+                    let data = &input[offset as usize..(offset + len) as usize];
+                    let cv = stack.tree.subtree_cv(data, offset);
+                    stack.push_span("test", cv, offset, len).unwrap();
+                }
+                assert_eq!(stack.root(), expected, "{n}");
+            }
+        }
+    }
+
+    #[cfg(all(feature = "bao", feature = "hazmat"))]
+    #[test]
+    fn chunk_cvs_match_bao_parents() {
+        let input = test_input(4096);
+        let (outboard, _) = bao::encode::outboard(&input);
+        let cvs: Vec<ChainingValue> = input
+            .chunks(CHUNK_LEN)
+            .enumerate()
+            .map(|(i, chunk)| *chunk_cv(chunk, (i * CHUNK_LEN) as u64, false).as_bytes())
+            .collect();
+        // Pre-order: the root's children, then the left parent's, then the right's.
+        let level = merge_cv_level(&cvs, Mode::Hash);
+        assert_eq!(
+            outboard[HEADER_LEN..],
+            [level.concat(), cvs[..2].concat(), cvs[2..].concat()].concat()
+        );
+    }
+
+    #[test]
+    fn mmr_proofs_round_trip_and_reject_tampering() {
+        let digests: Vec<blake3::Hash> =
+            (0..37u32).map(|i| blake3::hash(&i.to_le_bytes())).collect();
+        let (other, wrong_root) = (blake3::hash(b"other"), blake3::hash(b"root"));
+        let mut mmr = Mmr::default();
+        for (count, digest) in (1..).zip(&digests) {
+            mmr.push_leaf(digest);
+            assert_eq!(mmr.leaf_count(), count);
+            let root = mmr.root_hash();
+            for (index, leaf) in (0..count).zip(&digests) {
+                let proof = mmr.prove_nodes(index).unwrap();
+                assert!(
+                    mmr_verify(&root, count, index, leaf, &proof),
+                    "{count} {index}"
+                );
+                assert!(!mmr_verify(&root, count, index, &other, &proof));
+                assert!(!mmr_verify(&wrong_root, count, index, leaf, &proof));
+                assert!(!mmr_verify(&root, count + 1, index, leaf, &proof));
+                if count > 1 {
+                    let next = (index + 1) % count;
+                    assert!(!mmr_verify(&root, count, next, leaf, &proof));
+                }
+                for i in (0..proof.len()).step_by(OUT_LEN) {
+                    let mut tampered = proof.clone();
+                    tampered[i] ^= 1;
+                    assert!(!mmr_verify(&root, count, index, leaf, &tampered));
+                    assert!(!mmr_verify(&root, count, index, leaf, &proof[OUT_LEN..]));
+                }
+            }
+            assert!(mmr.prove_nodes(count).is_none());
+            assert!(!mmr_verify(&root, count, count, &digests[0], &[]));
+        }
+        // Eight leaves make one peak, so a proof is just the three siblings.
+        let mut eight = Mmr::default();
+        for digest in &digests[..8] {
+            eight.push_leaf(digest);
+        }
+        assert_eq!(eight.peak_nodes().len(), 1);
+        assert_eq!(eight.prove_nodes(5).unwrap().len(), 3 * OUT_LEN);
+    }
+}